@@ -18,6 +18,63 @@ use serde::{Deserialize, Serialize};
 #[serde(untagged)]
 pub enum DiscloseMultipleTransactionsError {
     Status400(models::Error),
+    Status401(models::Error),
+    Status404(models::Error),
+    Status408(models::Error),
+    Status412(models::Error),
+    Status413(models::Error),
+    Status422(models::Error),
+    Status429(models::Error),
+    Status500(models::Error),
+    Status502(models::Error),
+    Status503(models::Error),
+    UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`create_disclosure_job`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CreateDisclosureJobError {
+    Status400(models::Error),
+    Status401(models::Error),
+    Status404(models::Error),
+    Status408(models::Error),
+    Status413(models::Error),
+    Status422(models::Error),
+    Status429(models::Error),
+    Status500(models::Error),
+    Status503(models::Error),
+    UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`get_disclosure_job`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetDisclosureJobError {
+    Status401(models::Error),
+    Status404(models::Error),
+    Status500(models::Error),
+    UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`register_wallet`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegisterWalletError {
+    Status400(models::Error),
+    Status401(models::Error),
+    Status408(models::Error),
+    Status413(models::Error),
+    Status500(models::Error),
+    UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`revoke_wallet`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RevokeWalletError {
+    Status401(models::Error),
+    Status404(models::Error),
     Status500(models::Error),
     UnknownValue(serde_json::Value),
 }
@@ -27,16 +84,136 @@ pub enum DiscloseMultipleTransactionsError {
 #[serde(untagged)]
 pub enum DiscloseSingleTransactionError {
     Status400(models::Error),
+    Status401(models::Error),
+    Status404(models::Error),
+    Status408(models::Error),
+    Status412(models::Error),
+    Status413(models::Error),
+    Status429(models::Error),
+    Status500(models::Error),
+    Status502(models::Error),
+    Status503(models::Error),
+    UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`get_disclosed_transaction`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetDisclosedTransactionError {
+    Status400(models::Error),
+    Status401(models::Error),
+    Status404(models::Error),
+    Status408(models::Error),
+    Status429(models::Error),
     Status500(models::Error),
+    Status502(models::Error),
+    Status503(models::Error),
     UnknownValue(serde_json::Value),
 }
 
+pub async fn create_disclosure_job(
+    configuration: &configuration::Configuration,
+    disclosure_request_multiple: models::DisclosureRequestMultiple,
+) -> Result<models::DisclosureJob, Error<CreateDisclosureJobError>> {
+    // add a prefix to parameters to efficiently prevent name collisions
+    let p_disclosure_request_multiple = disclosure_request_multiple;
+
+    let uri_str = format!("{}/jobs/disclose", configuration.base_path);
+    let mut req_builder = configuration
+        .client
+        .request(reqwest::Method::POST, &uri_str);
+
+    if let Some(ref user_agent) = configuration.user_agent {
+        req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
+    req_builder = req_builder.json(&p_disclosure_request_multiple);
+
+    let req = req_builder.build()?;
+    let resp = configuration.client.execute(req).await?;
+
+    let status = resp.status();
+
+    if !status.is_client_error() && !status.is_server_error() {
+        let content = resp.text().await?;
+        serde_json::from_str(&content).map_err(Error::from)
+    } else {
+        let content = resp.text().await?;
+        let entity: Option<CreateDisclosureJobError> = serde_json::from_str(&content).ok();
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content,
+            entity,
+        }))
+    }
+}
+
+pub async fn get_disclosure_job(
+    configuration: &configuration::Configuration,
+    job_id: &str,
+) -> Result<models::DisclosureJob, Error<GetDisclosureJobError>> {
+    // add a prefix to parameters to efficiently prevent name collisions
+    let p_job_id = job_id;
+
+    let uri_str = format!(
+        "{}/jobs/{jobId}",
+        configuration.base_path,
+        jobId = crate::apis::urlencode(p_job_id)
+    );
+    let mut req_builder = configuration.client.request(reqwest::Method::GET, &uri_str);
+
+    if let Some(ref user_agent) = configuration.user_agent {
+        req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
+
+    let req = req_builder.build()?;
+    let resp = configuration.client.execute(req).await?;
+
+    let status = resp.status();
+
+    if !status.is_client_error() && !status.is_server_error() {
+        let content = resp.text().await?;
+        serde_json::from_str(&content).map_err(Error::from)
+    } else {
+        let content = resp.text().await?;
+        let entity: Option<GetDisclosureJobError> = serde_json::from_str(&content).ok();
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content,
+            entity,
+        }))
+    }
+}
+
 pub async fn disclose_multiple_transactions(
     configuration: &configuration::Configuration,
     disclosure_request_multiple: models::DisclosureRequestMultiple,
-) -> Result<Vec<models::DisclosedTransactionResult>, Error<DiscloseMultipleTransactionsError>> {
+    if_none_match: Option<&str>,
+) -> Result<models::DisclosedTransactionResult, Error<DiscloseMultipleTransactionsError>> {
     // add a prefix to parameters to efficiently prevent name collisions
     let p_disclosure_request_multiple = disclosure_request_multiple;
+    let p_if_none_match = if_none_match;
 
     let uri_str = format!("{}/disclose/transactions", configuration.base_path);
     let mut req_builder = configuration
@@ -46,6 +223,20 @@ pub async fn disclose_multiple_transactions(
     if let Some(ref user_agent) = configuration.user_agent {
         req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
     }
+    if let Some(param_value) = p_if_none_match {
+        req_builder = req_builder.header("If-None-Match", param_value.to_string());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
     req_builder = req_builder.json(&p_disclosure_request_multiple);
 
     let req = req_builder.build()?;
@@ -70,9 +261,11 @@ pub async fn disclose_multiple_transactions(
 pub async fn disclose_single_transaction(
     configuration: &configuration::Configuration,
     disclosure_request_single: models::DisclosureRequestSingle,
+    if_none_match: Option<&str>,
 ) -> Result<models::Transaction, Error<DiscloseSingleTransactionError>> {
     // add a prefix to parameters to efficiently prevent name collisions
     let p_disclosure_request_single = disclosure_request_single;
+    let p_if_none_match = if_none_match;
 
     let uri_str = format!("{}/disclose/transaction", configuration.base_path);
     let mut req_builder = configuration
@@ -82,6 +275,20 @@ pub async fn disclose_single_transaction(
     if let Some(ref user_agent) = configuration.user_agent {
         req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
     }
+    if let Some(param_value) = p_if_none_match {
+        req_builder = req_builder.header("If-None-Match", param_value.to_string());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
     req_builder = req_builder.json(&p_disclosure_request_single);
 
     let req = req_builder.build()?;
@@ -102,3 +309,155 @@ pub async fn disclose_single_transaction(
         }))
     }
 }
+
+pub async fn get_disclosed_transaction(
+    configuration: &configuration::Configuration,
+    transaction_hash: &str,
+    x_wallet_id: &str,
+    if_none_match: Option<&str>,
+) -> Result<models::Transaction, Error<GetDisclosedTransactionError>> {
+    // add a prefix to parameters to efficiently prevent name collisions
+    let p_transaction_hash = transaction_hash;
+    let p_x_wallet_id = x_wallet_id;
+    let p_if_none_match = if_none_match;
+
+    let uri_str = format!(
+        "{}/disclose/transaction/{transactionHash}",
+        configuration.base_path,
+        transactionHash = crate::apis::urlencode(p_transaction_hash)
+    );
+    let mut req_builder = configuration.client.request(reqwest::Method::GET, &uri_str);
+
+    if let Some(ref user_agent) = configuration.user_agent {
+        req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+    }
+    req_builder = req_builder.header("X-Wallet-Id", p_x_wallet_id.to_string());
+    if let Some(param_value) = p_if_none_match {
+        req_builder = req_builder.header("If-None-Match", param_value.to_string());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
+
+    let req = req_builder.build()?;
+    let resp = configuration.client.execute(req).await?;
+
+    let status = resp.status();
+
+    if !status.is_client_error() && !status.is_server_error() {
+        let content = resp.text().await?;
+        serde_json::from_str(&content).map_err(Error::from)
+    } else {
+        let content = resp.text().await?;
+        let entity: Option<GetDisclosedTransactionError> = serde_json::from_str(&content).ok();
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content,
+            entity,
+        }))
+    }
+}
+
+pub async fn register_wallet(
+    configuration: &configuration::Configuration,
+    wallet_registration_request: models::WalletRegistrationRequest,
+) -> Result<models::Wallet, Error<RegisterWalletError>> {
+    // add a prefix to parameters to efficiently prevent name collisions
+    let p_wallet_registration_request = wallet_registration_request;
+
+    let uri_str = format!("{}/wallets", configuration.base_path);
+    let mut req_builder = configuration
+        .client
+        .request(reqwest::Method::POST, &uri_str);
+
+    if let Some(ref user_agent) = configuration.user_agent {
+        req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
+    req_builder = req_builder.json(&p_wallet_registration_request);
+
+    let req = req_builder.build()?;
+    let resp = configuration.client.execute(req).await?;
+
+    let status = resp.status();
+
+    if !status.is_client_error() && !status.is_server_error() {
+        let content = resp.text().await?;
+        serde_json::from_str(&content).map_err(Error::from)
+    } else {
+        let content = resp.text().await?;
+        let entity: Option<RegisterWalletError> = serde_json::from_str(&content).ok();
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content,
+            entity,
+        }))
+    }
+}
+
+pub async fn revoke_wallet(
+    configuration: &configuration::Configuration,
+    wallet_id: &str,
+) -> Result<(), Error<RevokeWalletError>> {
+    // add a prefix to parameters to efficiently prevent name collisions
+    let p_wallet_id = wallet_id;
+
+    let uri_str = format!(
+        "{}/wallets/{walletId}",
+        configuration.base_path,
+        walletId = crate::apis::urlencode(p_wallet_id)
+    );
+    let mut req_builder = configuration
+        .client
+        .request(reqwest::Method::DELETE, &uri_str);
+
+    if let Some(ref user_agent) = configuration.user_agent {
+        req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+    }
+    if let Some(ref apikey) = configuration.api_key {
+        let key = apikey.key.clone();
+        let value = match apikey.prefix {
+            Some(ref prefix) => format!("{} {}", prefix, key),
+            None => key,
+        };
+        req_builder = req_builder.header("X-API-Key", value);
+    };
+    if let Some(ref token) = configuration.bearer_access_token {
+        req_builder = req_builder.bearer_auth(token.to_owned());
+    };
+
+    let req = req_builder.build()?;
+    let resp = configuration.client.execute(req).await?;
+
+    let status = resp.status();
+
+    if !status.is_client_error() && !status.is_server_error() {
+        Ok(())
+    } else {
+        let content = resp.text().await?;
+        let entity: Option<RevokeWalletError> = serde_json::from_str(&content).ok();
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            content,
+            entity,
+        }))
+    }
+}
@@ -0,0 +1,34 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallbackAttempt {
+    /// When the attempt was made, in unix seconds
+    #[serde(rename = "attemptedAt")]
+    pub attempted_at: i64,
+    /// HTTP status returned by the callback URL, if it responded
+    #[serde(rename = "statusCode", skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<i32>,
+    /// Why the attempt failed, if it did
+    #[serde(rename = "error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CallbackAttempt {
+    pub fn new(attempted_at: i64) -> CallbackAttempt {
+        CallbackAttempt {
+            attempted_at,
+            status_code: None,
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallbackDelivery {
+    /// URL the job outcome is delivered to
+    #[serde(rename = "url")]
+    pub url: String,
+    #[serde(rename = "status")]
+    pub status: Status,
+    /// Delivery attempts made so far, oldest first
+    #[serde(rename = "attempts")]
+    pub attempts: Vec<models::CallbackAttempt>,
+}
+
+impl CallbackDelivery {
+    pub fn new(
+        url: String,
+        status: Status,
+        attempts: Vec<models::CallbackAttempt>,
+    ) -> CallbackDelivery {
+        CallbackDelivery {
+            url,
+            status,
+            attempts,
+        }
+    }
+}
+/// Status of the delivery
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "delivered")]
+    Delivered,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl Default for Status {
+    fn default() -> Status {
+        Self::Pending
+    }
+}
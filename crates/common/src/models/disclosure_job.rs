@@ -0,0 +1,70 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureJob {
+    /// Identifier used to poll the job
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "status")]
+    pub status: Status,
+    /// When the job was submitted, in unix seconds
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    /// When the job last changed status, in unix seconds
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+    #[serde(rename = "progress", skip_serializing_if = "Option::is_none")]
+    pub progress: Option<models::DisclosureJobProgress>,
+    #[serde(rename = "result", skip_serializing_if = "Option::is_none")]
+    pub result: Option<models::DisclosedTransactionResult>,
+    #[serde(rename = "error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<models::Error>,
+    #[serde(rename = "callback", skip_serializing_if = "Option::is_none")]
+    pub callback: Option<models::CallbackDelivery>,
+}
+
+impl DisclosureJob {
+    pub fn new(job_id: String, status: Status, created_at: i64, updated_at: i64) -> DisclosureJob {
+        DisclosureJob {
+            job_id,
+            status,
+            created_at,
+            updated_at,
+            progress: None,
+            result: None,
+            error: None,
+            callback: None,
+        }
+    }
+}
+/// Status of the job, `queued` while it waits for a worker or a sync slot. `result` is set once `completed`, and `error` once `failed`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "queued")]
+    Queued,
+    #[serde(rename = "syncing")]
+    Syncing,
+    #[serde(rename = "disclosing")]
+    Disclosing,
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl Default for Status {
+    fn default() -> Status {
+        Self::Queued
+    }
+}
@@ -12,21 +12,26 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DisclosureRequestMultiple {
-    /// Full viewing key used to disclose transactions
-    #[serde(rename = "fullViewingKey")]
-    pub full_viewing_key: String,
+    /// Full viewing key used to disclose transactions. Exactly one of `fullViewingKey` and `walletId` is required.
+    #[serde(rename = "fullViewingKey", skip_serializing_if = "Option::is_none")]
+    pub full_viewing_key: Option<String>,
+    /// Handle of a wallet registered with `POST /wallets`, used instead of the full viewing key
+    #[serde(rename = "walletId", skip_serializing_if = "Option::is_none")]
+    pub wallet_id: Option<String>,
     #[serde(rename = "transactionHashes")]
     pub transaction_hashes: Vec<String>,
+    /// URL the outcome is POSTed to once done, only supported by `POST /jobs/disclose`. It must point to a public address unless the server allows its host, and redirects aren't followed
+    #[serde(rename = "callbackUrl", skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
 }
 
 impl DisclosureRequestMultiple {
-    pub fn new(
-        full_viewing_key: String,
-        transaction_hashes: Vec<String>,
-    ) -> DisclosureRequestMultiple {
+    pub fn new(transaction_hashes: Vec<String>) -> DisclosureRequestMultiple {
         DisclosureRequestMultiple {
-            full_viewing_key,
+            full_viewing_key: None,
+            wallet_id: None,
             transaction_hashes,
+            callback_url: None,
         }
     }
 }
@@ -12,18 +12,22 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DisclosureRequestSingle {
-    /// Full viewing key used to disclose the transaction
-    #[serde(rename = "fullViewingKey")]
-    pub full_viewing_key: String,
+    /// Full viewing key used to disclose the transaction. Exactly one of `fullViewingKey` and `walletId` is required.
+    #[serde(rename = "fullViewingKey", skip_serializing_if = "Option::is_none")]
+    pub full_viewing_key: Option<String>,
+    /// Handle of a wallet registered with `POST /wallets`, used instead of the full viewing key
+    #[serde(rename = "walletId", skip_serializing_if = "Option::is_none")]
+    pub wallet_id: Option<String>,
     /// Transaction hash to disclose
     #[serde(rename = "transactionHash")]
     pub transaction_hash: String,
 }
 
 impl DisclosureRequestSingle {
-    pub fn new(full_viewing_key: String, transaction_hash: String) -> DisclosureRequestSingle {
+    pub fn new(transaction_hash: String) -> DisclosureRequestSingle {
         DisclosureRequestSingle {
-            full_viewing_key,
+            full_viewing_key: None,
+            wallet_id: None,
             transaction_hash,
         }
     }
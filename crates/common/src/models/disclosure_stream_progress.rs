@@ -0,0 +1,30 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureStreamProgress {
+    /// Height the wallet's view is synced to
+    #[serde(rename = "syncedHeight")]
+    pub synced_height: u64,
+    /// Latest block height known to the node
+    #[serde(rename = "latestHeight")]
+    pub latest_height: u64,
+}
+
+impl DisclosureStreamProgress {
+    pub fn new(synced_height: u64, latest_height: u64) -> DisclosureStreamProgress {
+        DisclosureStreamProgress {
+            synced_height,
+            latest_height,
+        }
+    }
+}
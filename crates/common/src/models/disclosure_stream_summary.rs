@@ -0,0 +1,34 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureStreamSummary {
+    /// Height the wallet's view was synced to when the transactions were disclosed
+    #[serde(rename = "syncedHeight")]
+    pub synced_height: u64,
+    /// Number of transactions that were disclosed
+    #[serde(rename = "disclosed")]
+    pub disclosed: u32,
+    /// Number of transactions that failed to disclose
+    #[serde(rename = "failed")]
+    pub failed: u32,
+}
+
+impl DisclosureStreamSummary {
+    pub fn new(synced_height: u64, disclosed: u32, failed: u32) -> DisclosureStreamSummary {
+        DisclosureStreamSummary {
+            synced_height,
+            disclosed,
+            failed,
+        }
+    }
+}
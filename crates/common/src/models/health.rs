@@ -0,0 +1,45 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    #[serde(rename = "status")]
+    pub status: Status,
+    /// Time the probe was answered
+    #[serde(rename = "timestamp")]
+    pub timestamp: String,
+    /// Version of the server
+    #[serde(rename = "version")]
+    pub version: String,
+}
+
+impl Health {
+    pub fn new(status: Status, timestamp: String, version: String) -> Health {
+        Health {
+            status,
+            timestamp,
+            version,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "ok")]
+    Ok,
+}
+
+impl Default for Status {
+    fn default() -> Status {
+        Self::Ok
+    }
+}
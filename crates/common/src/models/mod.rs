@@ -1,5 +1,12 @@
+// generated by openapi-generator, which writes out explicit Default impls for enums
+#![allow(clippy::derivable_impls)]
+
 pub mod asset;
 pub use self::asset::Asset;
+pub mod callback_attempt;
+pub use self::callback_attempt::CallbackAttempt;
+pub mod callback_delivery;
+pub use self::callback_delivery::CallbackDelivery;
 pub mod counterparty;
 pub use self::counterparty::Counterparty;
 pub mod disclosed_transaction_result;
@@ -10,13 +17,29 @@ pub mod disclosed_transaction_result_disclosure_transactions;
 pub use self::disclosed_transaction_result_disclosure_transactions::DisclosedTransactionResultDisclosureTransactions;
 pub mod disclosure_error;
 pub use self::disclosure_error::DisclosureError;
+pub mod disclosure_job;
+pub use self::disclosure_job::DisclosureJob;
+pub mod disclosure_job_progress;
+pub use self::disclosure_job_progress::DisclosureJobProgress;
 pub mod disclosure_request_multiple;
 pub use self::disclosure_request_multiple::DisclosureRequestMultiple;
 pub mod disclosure_request_single;
 pub use self::disclosure_request_single::DisclosureRequestSingle;
+pub mod disclosure_stream_progress;
+pub use self::disclosure_stream_progress::DisclosureStreamProgress;
+pub mod disclosure_stream_summary;
+pub use self::disclosure_stream_summary::DisclosureStreamSummary;
 pub mod error;
 pub use self::error::Error;
+pub mod health;
+pub use self::health::Health;
 pub mod metadata;
 pub use self::metadata::Metadata;
+pub mod readiness;
+pub use self::readiness::Readiness;
 pub mod transaction;
 pub use self::transaction::Transaction;
+pub mod wallet;
+pub use self::wallet::Wallet;
+pub mod wallet_registration_request;
+pub use self::wallet_registration_request::WalletRegistrationRequest;
@@ -0,0 +1,55 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Readiness {
+    #[serde(rename = "status")]
+    pub status: Status,
+    /// Time the probe was answered
+    #[serde(rename = "timestamp")]
+    pub timestamp: String,
+    /// Chain id reported by the node, absent if it couldn't be reached
+    #[serde(rename = "chainId", skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<String>,
+    /// Latest block height reported by the node, absent if it couldn't be reached
+    #[serde(rename = "latestHeight", skip_serializing_if = "Option::is_none")]
+    pub latest_height: Option<u64>,
+    /// Why the server isn't ready, only set when `unavailable`
+    #[serde(rename = "reason", skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Readiness {
+    pub fn new(status: Status, timestamp: String) -> Readiness {
+        Readiness {
+            status,
+            timestamp,
+            chain_id: None,
+            latest_height: None,
+            reason: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "ready")]
+    Ready,
+    #[serde(rename = "unavailable")]
+    Unavailable,
+}
+
+impl Default for Status {
+    fn default() -> Status {
+        Self::Ready
+    }
+}
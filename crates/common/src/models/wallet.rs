@@ -0,0 +1,24 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wallet {
+    /// Opaque handle used in place of the full viewing key in disclosure requests
+    #[serde(rename = "walletId")]
+    pub wallet_id: String,
+}
+
+impl Wallet {
+    pub fn new(wallet_id: String) -> Wallet {
+        Wallet { wallet_id }
+    }
+}
@@ -0,0 +1,24 @@
+/*
+ * Privacy Transaction Disclosure API
+ *
+ * API specification for selective disclosure of privacy-preserving transactions across various protocols including Penumbra and Solana Confidential Transactions.
+ *
+ * The version of the OpenAPI document: 1.0.0
+ * Contact: support@example.com
+ * Generated by: https://openapi-generator.tech
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalletRegistrationRequest {
+    /// Full viewing key to register
+    #[serde(rename = "fullViewingKey")]
+    pub full_viewing_key: String,
+}
+
+impl WalletRegistrationRequest {
+    pub fn new(full_viewing_key: String) -> WalletRegistrationRequest {
+        WalletRegistrationRequest { full_viewing_key }
+    }
+}
@@ -1,13 +1,73 @@
 use {
     std::str::FromStr,
-    tracing_subscriber::{filter::LevelFilter, prelude::*, EnvFilter, Layer},
+    tracing_subscriber::{filter::LevelFilter, prelude::*, EnvFilter, Layer, Registry},
 };
 
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
 /// initializes logging capabilities but adds a variety of customization, including file+line which sourced the log,
 /// a tokio-console used for monitoring async tasks, as well as log-level filtration
 pub fn init_log(level: &str, file: &str) {
-    let mut layers = Vec::with_capacity(2);
-    let level_filter = LevelFilter::from_level(tracing::Level::from_str(level).unwrap());
+    init_layers(level, file, None)
+}
+
+/// Like [`init_log`], additionally exporting spans to the OTLP/HTTP collector at `endpoint`
+///
+/// Spans are exported in batches in the background, the returned guard flushes
+/// the remaining ones when it is dropped.
+#[cfg(feature = "otlp")]
+pub fn init_log_with_otlp(
+    level: &str,
+    file: &str,
+    service_name: &str,
+    endpoint: &str,
+) -> Result<OtlpGuard, opentelemetry_otlp::ExporterBuildError> {
+    use {
+        opentelemetry::trace::TracerProvider,
+        opentelemetry_otlp::{SpanExporter, WithExportConfig},
+        opentelemetry_sdk::{trace::SdkTracerProvider, Resource},
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build();
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(service_name.to_string()))
+        .with_filter(level_filter(level))
+        .boxed();
+    init_layers(level, file, Some(layer));
+    Ok(OtlpGuard(provider))
+}
+
+/// Flushes the spans that haven't been exported yet when dropped
+#[cfg(feature = "otlp")]
+pub struct OtlpGuard(opentelemetry_sdk::trace::SdkTracerProvider);
+
+#[cfg(feature = "otlp")]
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.shutdown() {
+            log::warn!("failed to flush spans {err:#?}");
+        }
+    }
+}
+
+fn level_filter(level: &str) -> LevelFilter {
+    LevelFilter::from_level(tracing::Level::from_str(level).unwrap())
+}
+
+fn init_layers(level: &str, file: &str, extra: Option<BoxedLayer>) {
+    let mut layers = Vec::with_capacity(3);
+    let level_filter = level_filter(level);
 
     layers.push(
         tracing_subscriber::fmt::layer()
@@ -31,6 +91,7 @@ pub fn init_log(level: &str, file: &str) {
                 .boxed(),
         );
     }
+    layers.extend(extra);
     if let Err(err) = tracing_subscriber::registry().with(layers).try_init() {
         log::warn!("global subscriber already registered {err:#?}");
     }
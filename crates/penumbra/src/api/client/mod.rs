@@ -3,14 +3,18 @@ use {
     common::{
         apis::{
             configuration::Configuration,
-            default_api::{disclose_multiple_transactions, disclose_single_transaction},
+            default_api::{
+                create_disclosure_job, disclose_multiple_transactions, disclose_single_transaction,
+                get_disclosure_job, register_wallet, revoke_wallet,
+            },
         },
         models::{
-            DisclosedTransactionResult, DisclosureRequestMultiple, DisclosureRequestSingle,
-            Transaction,
+            disclosure_job::Status, DisclosedTransactionResult, DisclosureJob,
+            DisclosureRequestMultiple, DisclosureRequestSingle, Transaction,
+            WalletRegistrationRequest,
         },
     },
-    std::sync::Arc,
+    std::{sync::Arc, time::Duration},
 };
 
 #[derive(Clone)]
@@ -24,9 +28,11 @@ impl ApiClient {
         disclose_single_transaction(
             &self.0,
             DisclosureRequestSingle {
-                full_viewing_key: fvk,
+                full_viewing_key: Some(fvk),
+                wallet_id: None,
                 transaction_hash: tx_hash,
             },
+            None,
         )
         .await
         .with_context(|| "failed to send request")
@@ -35,15 +41,297 @@ impl ApiClient {
         &self,
         tx_hashes: Vec<String>,
         fvk: String,
-    ) -> Result<Vec<DisclosedTransactionResult>> {
+    ) -> Result<DisclosedTransactionResult> {
         disclose_multiple_transactions(
             &self.0,
             DisclosureRequestMultiple {
+                full_viewing_key: Some(fvk),
+                wallet_id: None,
+                transaction_hashes: tx_hashes,
+                callback_url: None,
+            },
+            None,
+        )
+        .await
+        .with_context(|| "failed to send request")
+    }
+    /// Registers `fvk` with the api, returning the wallet handle to disclose with
+    pub async fn register_wallet(&self, fvk: String) -> Result<String> {
+        register_wallet(
+            &self.0,
+            WalletRegistrationRequest {
                 full_viewing_key: fvk,
+            },
+        )
+        .await
+        .map(|wallet| wallet.wallet_id)
+        .with_context(|| "failed to send request")
+    }
+    pub async fn revoke_wallet(&self, wallet_id: &str) -> Result<()> {
+        revoke_wallet(&self.0, wallet_id)
+            .await
+            .with_context(|| "failed to send request")
+    }
+    pub async fn disclose_wallet_transaction(
+        &self,
+        tx_hash: String,
+        wallet_id: String,
+    ) -> Result<Transaction> {
+        disclose_single_transaction(
+            &self.0,
+            DisclosureRequestSingle {
+                full_viewing_key: None,
+                wallet_id: Some(wallet_id),
+                transaction_hash: tx_hash,
+            },
+            None,
+        )
+        .await
+        .with_context(|| "failed to send request")
+    }
+    pub async fn disclose_wallet_transactions(
+        &self,
+        tx_hashes: Vec<String>,
+        wallet_id: String,
+    ) -> Result<DisclosedTransactionResult> {
+        disclose_multiple_transactions(
+            &self.0,
+            DisclosureRequestMultiple {
+                full_viewing_key: None,
+                wallet_id: Some(wallet_id),
                 transaction_hashes: tx_hashes,
+                callback_url: None,
             },
+            None,
         )
         .await
         .with_context(|| "failed to send request")
     }
+    /// Submits a batch disclosure to run in the background, see [`ApiClient::wait_for_job`]
+    pub async fn submit_disclosure_job(
+        &self,
+        req: DisclosureRequestMultiple,
+    ) -> Result<DisclosureJob> {
+        create_disclosure_job(&self.0, req)
+            .await
+            .with_context(|| "failed to send request")
+    }
+    pub async fn disclosure_job(&self, job_id: &str) -> Result<DisclosureJob> {
+        get_disclosure_job(&self.0, job_id)
+            .await
+            .with_context(|| "failed to send request")
+    }
+    /// Polls the job every `poll_interval` until it has completed or failed
+    pub async fn wait_for_job(
+        &self,
+        job_id: &str,
+        poll_interval: Duration,
+    ) -> Result<DisclosureJob> {
+        loop {
+            let job = self.disclosure_job(job_id).await?;
+            if matches!(job.status, Status::Completed | Status::Failed) {
+                return Ok(job);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "api-server"))]
+mod test {
+    use {
+        super::*,
+        crate::api::server::{
+            auth::{ApiKeyConfig, AuthConfig, Authenticator, BearerConfig},
+            router::{self, AppState},
+            test_util::FVK,
+            ServerOptions,
+        },
+        common::apis::{
+            configuration::ApiKey, default_api::DiscloseSingleTransactionError, Error as ApiError,
+        },
+        reqwest::StatusCode,
+    };
+
+    const TX_HASH: &str = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+
+    /// Serves the api on an ephemeral port and returns a client pointed at it
+    async fn spawn_api() -> Arc<ApiClient> {
+        ApiClient::new(Configuration {
+            base_path: serve(ServerOptions::default()).await,
+            ..Default::default()
+        })
+    }
+
+    /// Serves the api on an ephemeral port and returns its base url
+    async fn serve(opts: ServerOptions) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = router::new(Arc::new(
+            AppState::new("http://localhost:8080/".to_string(), &opts).unwrap(),
+        ));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    /// Returns the status of the error response a disclosure request failed with
+    fn response_status(err: &anyhow::Error) -> StatusCode {
+        let Some(ApiError::ResponseError(res)) =
+            err.downcast_ref::<ApiError<DiscloseSingleTransactionError>>()
+        else {
+            panic!("expected an error response, got {err:#}");
+        };
+        res.status
+    }
+
+    #[tokio::test]
+    async fn test_api_client_disclose_transaction() {
+        let client = spawn_api().await;
+        let tx_info = client
+            .disclose_transaction(TX_HASH.to_string(), FVK.to_string())
+            .await
+            .unwrap();
+        assert_eq!(tx_info.transaction_hash, TX_HASH);
+        assert_eq!(tx_info.chain_id, "penumbra-testnet-phobos-x3b26d34a");
+        assert_eq!(tx_info.counterparties.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_api_client_disclose_transactions() {
+        let client = spawn_api().await;
+        let missing_hash = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b2517fffffffffccff";
+        let res = client
+            .disclose_transactions(
+                vec![TX_HASH.to_string(), missing_hash.to_string()],
+                FVK.to_string(),
+            )
+            .await
+            .unwrap();
+
+        let txns = res.disclosure_transactions.unwrap().transactions;
+        assert_eq!(txns.len(), 1);
+        assert_eq!(txns[0].transaction_hash, TX_HASH);
+
+        let errors = res.disclosure_errors.unwrap().errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].transaction_hash, missing_hash);
+    }
+
+    #[tokio::test]
+    async fn test_api_client_invalid_fvk() {
+        let client = spawn_api().await;
+        let err = client
+            .disclose_transaction(TX_HASH.to_string(), "not a viewing key".to_string())
+            .await
+            .unwrap_err();
+
+        let Some(ApiError::ResponseError(res)) =
+            err.downcast_ref::<ApiError<DiscloseSingleTransactionError>>()
+        else {
+            panic!("expected an error response, got {err:#}");
+        };
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+        assert!(matches!(
+            res.entity,
+            Some(DiscloseSingleTransactionError::Status400(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_api_client_sends_credentials() {
+        let auth = AuthConfig {
+            api_keys: vec![],
+            bearer: Some(BearerConfig {
+                secret: "0123456789abcdef0123456789abcdef".to_string(),
+            }),
+        };
+        let token = Authenticator::new(&auth)
+            .issue_token("auditor", Duration::from_secs(60))
+            .unwrap();
+        let base_path = serve(ServerOptions {
+            auth: Some(AuthConfig {
+                api_keys: vec![ApiKeyConfig {
+                    name: "ops".to_string(),
+                    key: "test-api-key".to_string(),
+                    rate_limit: None,
+                }],
+                ..auth
+            }),
+            ..Default::default()
+        })
+        .await;
+
+        // the fvk is rejected by the handler, so a 400 means the request was authenticated
+        let credentials = [
+            (None, None, StatusCode::UNAUTHORIZED),
+            (
+                Some(ApiKey {
+                    prefix: None,
+                    key: "test-api-key".to_string(),
+                }),
+                None,
+                StatusCode::BAD_REQUEST,
+            ),
+            (None, Some(token), StatusCode::BAD_REQUEST),
+        ];
+        for (api_key, bearer_access_token, status) in credentials {
+            let client = ApiClient::new(Configuration {
+                base_path: base_path.clone(),
+                api_key,
+                bearer_access_token,
+                ..Default::default()
+            });
+            let err = client
+                .disclose_transaction(TX_HASH.to_string(), "not a viewing key".to_string())
+                .await
+                .unwrap_err();
+            assert_eq!(response_status(&err), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_client_disclose_by_wallet() {
+        let client = spawn_api().await;
+        let wallet_id = client.register_wallet(FVK.to_string()).await.unwrap();
+        let tx_info = client
+            .disclose_wallet_transaction(TX_HASH.to_string(), wallet_id.clone())
+            .await
+            .unwrap();
+        assert_eq!(tx_info.transaction_hash, TX_HASH);
+
+        client.revoke_wallet(&wallet_id).await.unwrap();
+        let err = client
+            .disclose_wallet_transaction(TX_HASH.to_string(), wallet_id)
+            .await
+            .unwrap_err();
+        assert_eq!(response_status(&err), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_api_client_disclosure_job() {
+        let client = spawn_api().await;
+        let job = client
+            .submit_disclosure_job(DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![TX_HASH.to_string()],
+                callback_url: None,
+            })
+            .await
+            .unwrap();
+        let job = client
+            .wait_for_job(&job.job_id, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(job.status, Status::Completed);
+
+        let txns = job
+            .result
+            .unwrap()
+            .disclosure_transactions
+            .unwrap()
+            .transactions;
+        assert_eq!(txns.len(), 1);
+        assert_eq!(txns[0].transaction_hash, TX_HASH);
+    }
 }
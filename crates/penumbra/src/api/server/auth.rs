@@ -0,0 +1,372 @@
+use {
+    crate::api::server::{error::ApiError, rate_limit::RateLimit, router::AppState},
+    anyhow::{Context, Result},
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine},
+    camino::Utf8Path,
+    hmac::{Hmac, Mac},
+    http::{header, HeaderMap},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Header carrying a static api key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Shortest accepted bearer token signing secret, in bytes
+const MIN_SECRET_LEN: usize = 32;
+
+/// Credentials accepted by the api server, loaded from a TOML file
+///
+/// ```toml
+/// [[api_keys]]
+/// name = "ops"
+/// key = "..."
+///
+/// [bearer]
+/// secret = "..."
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    pub bearer: Option<BearerConfig>,
+}
+
+/// A static api key, sent in the `x-api-key` header
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// identifies the caller in logs
+    pub name: String,
+    pub key: String,
+    /// replaces the server's default rate limit for this key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Secret used to sign and verify HS256 bearer tokens
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BearerConfig {
+    pub secret: String,
+}
+
+impl AuthConfig {
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        let cfg: Self =
+            toml::from_str(&contents).with_context(|| format!("failed to parse {path}"))?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.api_keys.is_empty() && self.bearer.is_none() {
+            anyhow::bail!("auth config must define at least one api key or a bearer secret");
+        }
+        if let Some(api_key) = self.api_keys.iter().find(|api_key| api_key.key.is_empty()) {
+            anyhow::bail!("api key {} is empty", api_key.name);
+        }
+        if let Some(api_key) = self.api_keys.iter().find(|api_key| {
+            api_key
+                .rate_limit
+                .is_some_and(|limit| limit.per_minute == 0 || limit.burst == 0)
+        }) {
+            anyhow::bail!("rate limit of api key {} must be at least 1", api_key.name);
+        }
+        if let Some(bearer) = &self.bearer {
+            if bearer.secret.len() < MIN_SECRET_LEN {
+                anyhow::bail!("bearer secret must be at least {MIN_SECRET_LEN} bytes");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Identity of an authenticated caller, available to handlers as a request extension
+///
+/// Identities are prefixed by how the caller authenticated, `key:` for api keys
+/// and `jwt:` for bearer tokens, so a token's subject never passes for an api key's name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Principal(String);
+
+impl Principal {
+    /// Caller authenticated with the api key named `name`
+    pub fn api_key(name: &str) -> Self {
+        Self(format!("key:{name}"))
+    }
+
+    /// Caller authenticated with a bearer token issued for `subject`
+    pub fn token(subject: &str) -> Self {
+        Self(format!("jwt:{subject}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Why a request was rejected, answered with `401 unauthorized`, see [`ApiError::Unauthorized`]
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("missing credentials")]
+    MissingCredentials,
+    #[error("invalid api key")]
+    InvalidApiKey,
+    #[error("invalid bearer token")]
+    InvalidToken,
+    #[error("bearer token expired")]
+    TokenExpired,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenHeader {
+    alg: String,
+    typ: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    /// expiry in unix seconds
+    exp: u64,
+}
+
+/// Verifies api keys and HS256 bearer tokens (JWTs) against an [`AuthConfig`]
+pub struct Authenticator {
+    /// sha256 of each api key -> its name, so keys are never compared directly
+    api_keys: HashMap<[u8; 32], String>,
+    bearer_secret: Option<Vec<u8>>,
+}
+
+impl Authenticator {
+    pub fn new(cfg: &AuthConfig) -> Self {
+        Self {
+            api_keys: cfg
+                .api_keys
+                .iter()
+                .map(|api_key| (Sha256::digest(&api_key.key).into(), api_key.name.clone()))
+                .collect(),
+            bearer_secret: cfg
+                .bearer
+                .as_ref()
+                .map(|bearer| bearer.secret.as_bytes().to_vec()),
+        }
+    }
+
+    /// Authenticates a request from its `x-api-key` or `Authorization: Bearer` header
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, AuthError> {
+        if let Some(api_key) = headers.get(API_KEY_HEADER) {
+            return self
+                .api_keys
+                .get(&<[u8; 32]>::from(Sha256::digest(api_key.as_bytes())))
+                .map(|name| Principal::api_key(name))
+                .ok_or(AuthError::InvalidApiKey);
+        }
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthError::MissingCredentials)?;
+        self.verify_token(token.trim())
+    }
+
+    /// Issues a bearer token for `subject` that is valid for `ttl`
+    pub fn issue_token(&self, subject: &str, ttl: Duration) -> Result<String> {
+        let secret = self
+            .bearer_secret
+            .as_ref()
+            .with_context(|| "auth config has no bearer secret")?;
+        let header = TokenHeader {
+            alg: "HS256".to_string(),
+            typ: "JWT".to_string(),
+        };
+        let claims = TokenClaims {
+            sub: subject.to_string(),
+            exp: unix_now().saturating_add(ttl.as_secs()),
+        };
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?)
+        );
+        let signature = token_mac(secret, &signing_input).finalize().into_bytes();
+        Ok(format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    fn verify_token(&self, token: &str) -> Result<Principal, AuthError> {
+        let secret = self.bearer_secret.as_ref().ok_or(AuthError::InvalidToken)?;
+        let (signing_input, signature) = token.rsplit_once('.').ok_or(AuthError::InvalidToken)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::InvalidToken)?;
+        token_mac(secret, signing_input)
+            .verify_slice(&signature)
+            .map_err(|_| AuthError::InvalidToken)?;
+
+        // the signature is valid, so the header and claims were issued by us
+        let (header, claims) = signing_input
+            .split_once('.')
+            .ok_or(AuthError::InvalidToken)?;
+        let header: TokenHeader = decode_segment(header)?;
+        if header.alg != "HS256" {
+            return Err(AuthError::InvalidToken);
+        }
+        let claims: TokenClaims = decode_segment(claims)?;
+        if claims.exp <= unix_now() {
+            return Err(AuthError::TokenExpired);
+        }
+        Ok(Principal::token(&claims.sub))
+    }
+}
+
+fn token_mac(secret: &[u8], signing_input: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts any key length");
+    mac.update(signing_input.as_bytes());
+    mac
+}
+
+fn decode_segment<T: serde::de::DeserializeOwned>(segment: &str) -> Result<T, AuthError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| AuthError::InvalidToken)?;
+    serde_json::from_slice(&bytes).map_err(|_| AuthError::InvalidToken)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+/// Middleware rejecting requests without valid credentials
+///
+/// The authenticated [`Principal`] is added to the request extensions.
+pub async fn require_auth(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(auth) = &state.auth else {
+        return Ok(next.run(req).await);
+    };
+    let principal = auth.authenticate(req.headers()).inspect_err(|err| {
+        log::warn!("rejected {} {}: {err}", req.method(), req.uri().path());
+    })?;
+    req.extensions_mut().insert(principal);
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, http::HeaderValue};
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(&AuthConfig {
+            api_keys: vec![ApiKeyConfig {
+                name: "ops".to_string(),
+                key: "test-api-key".to_string(),
+                rate_limit: None,
+            }],
+            bearer: Some(BearerConfig {
+                secret: "0123456789abcdef0123456789abcdef".to_string(),
+            }),
+        })
+    }
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_authenticate_api_key() {
+        let auth = authenticator();
+        let principal = auth
+            .authenticate(&headers(
+                header::HeaderName::from_static(API_KEY_HEADER),
+                "test-api-key",
+            ))
+            .unwrap();
+        assert_eq!(principal, Principal::api_key("ops"));
+        assert_eq!(principal.as_str(), "key:ops");
+
+        assert!(matches!(
+            auth.authenticate(&headers(
+                header::HeaderName::from_static(API_KEY_HEADER),
+                "wrong-key",
+            )),
+            Err(AuthError::InvalidApiKey)
+        ));
+        assert!(matches!(
+            auth.authenticate(&HeaderMap::new()),
+            Err(AuthError::MissingCredentials)
+        ));
+    }
+
+    #[test]
+    fn test_authenticate_bearer_token() {
+        let auth = authenticator();
+        let token = auth
+            .issue_token("auditor", Duration::from_secs(60))
+            .unwrap();
+        let principal = auth
+            .authenticate(&headers(header::AUTHORIZATION, &format!("Bearer {token}")))
+            .unwrap();
+        assert_eq!(principal, Principal::token("auditor"));
+        assert_eq!(principal.as_str(), "jwt:auditor");
+
+        // flipping the last signature character invalidates the token
+        let mut tampered = token.clone();
+        let last = if tampered.pop() == Some('A') {
+            'B'
+        } else {
+            'A'
+        };
+        tampered.push(last);
+        assert!(matches!(
+            auth.authenticate(&headers(
+                header::AUTHORIZATION,
+                &format!("Bearer {tampered}")
+            )),
+            Err(AuthError::InvalidToken)
+        ));
+
+        // tokens signed with another secret are rejected
+        let other = Authenticator::new(&AuthConfig {
+            api_keys: vec![],
+            bearer: Some(BearerConfig {
+                secret: "fedcba9876543210fedcba9876543210".to_string(),
+            }),
+        })
+        .issue_token("auditor", Duration::from_secs(60))
+        .unwrap();
+        assert!(matches!(
+            auth.authenticate(&headers(header::AUTHORIZATION, &format!("Bearer {other}"))),
+            Err(AuthError::InvalidToken)
+        ));
+
+        let expired = auth.issue_token("auditor", Duration::ZERO).unwrap();
+        assert!(matches!(
+            auth.authenticate(&headers(
+                header::AUTHORIZATION,
+                &format!("Bearer {expired}")
+            )),
+            Err(AuthError::TokenExpired)
+        ));
+    }
+}
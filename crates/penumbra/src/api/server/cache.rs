@@ -0,0 +1,165 @@
+use {
+    crate::metrics::metrics,
+    common::models::Transaction,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Mutex,
+    },
+};
+
+/// Transactions already disclosed, keyed by the FVK hash and the transaction hash
+///
+/// A transaction can't change once it is on chain, and neither can its
+/// disclosure to a given wallet, so repeated disclosures are served from the
+/// cache without syncing the wallet or querying the node. Only successful
+/// disclosures are cached. Once `capacity` disclosures are cached, the oldest
+/// ones are evicted first.
+pub struct DisclosureCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    disclosures: HashMap<CacheKey, CachedDisclosure>,
+    /// keys in the order they were inserted, oldest first
+    order: VecDeque<CacheKey>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    fvk_hash: String,
+    transaction_hash: String,
+}
+
+/// A disclosed transaction along with the height the wallet was synced to when it was disclosed
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedDisclosure {
+    pub transaction: Transaction,
+    pub synced_height: u64,
+}
+
+impl DisclosureCache {
+    /// Creates a cache holding up to `capacity` disclosures, nothing is cached if it is 0
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    /// Returns the cached disclosure of the normalized `transaction_hash` to the wallet with `fvk_hash`
+    pub fn get(&self, fvk_hash: &str, transaction_hash: &str) -> Option<CachedDisclosure> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let key = CacheKey {
+            fvk_hash: fvk_hash.to_string(),
+            transaction_hash: transaction_hash.to_string(),
+        };
+        let cached = self.entries.lock().unwrap().disclosures.get(&key).cloned();
+        metrics().record_cache_lookup(cached.is_some());
+        cached
+    }
+
+    /// Caches the disclosure of the normalized `transaction_hash` to the wallet with `fvk_hash`
+    pub fn insert(
+        &self,
+        fvk_hash: &str,
+        transaction_hash: &str,
+        transaction: &Transaction,
+        synced_height: u64,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = CacheKey {
+            fvk_hash: fvk_hash.to_string(),
+            transaction_hash: transaction_hash.to_string(),
+        };
+        let disclosure = CachedDisclosure {
+            transaction: transaction.clone(),
+            synced_height,
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if entries
+            .disclosures
+            .insert(key.clone(), disclosure)
+            .is_some()
+        {
+            return;
+        }
+        entries.order.push_back(key);
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.disclosures.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops every disclosure to the wallet with `fvk_hash`
+    pub fn remove_wallet(&self, fvk_hash: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.order.retain(|key| key.fvk_hash != fvk_hash);
+        entries
+            .disclosures
+            .retain(|key, _| key.fvk_hash != fvk_hash);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().disclosures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, common::models::transaction::Protocol};
+
+    fn transaction(hash: &str) -> Transaction {
+        Transaction::new(
+            hash.to_string(),
+            Protocol::Penumbra,
+            "penumbra-1".to_string(),
+            vec![],
+            "1745289093".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_disclosure_cache() {
+        let cache = DisclosureCache::new(2);
+        assert!(cache.get("wallet", "aa").is_none());
+
+        cache.insert("wallet", "aa", &transaction("aa"), 10);
+        let cached = cache.get("wallet", "aa").unwrap();
+        assert_eq!(cached.transaction, transaction("aa"));
+        assert_eq!(cached.synced_height, 10);
+        // disclosures are only shared with the wallet they were disclosed to
+        assert!(cache.get("other wallet", "aa").is_none());
+
+        // the oldest disclosure is evicted once the cache is full
+        cache.insert("wallet", "bb", &transaction("bb"), 11);
+        cache.insert("wallet", "aa", &transaction("aa"), 12);
+        cache.insert("wallet", "cc", &transaction("cc"), 12);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("wallet", "aa").is_none());
+        assert!(cache.get("wallet", "bb").is_some());
+        assert!(cache.get("wallet", "cc").is_some());
+
+        // a revoked wallet's disclosures are dropped, and only theirs
+        cache.insert("other wallet", "aa", &transaction("aa"), 12);
+        cache.remove_wallet("wallet");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("wallet", "cc").is_none());
+        assert!(cache.get("other wallet", "aa").is_some());
+
+        let disabled = DisclosureCache::new(0);
+        disabled.insert("wallet", "aa", &transaction("aa"), 10);
+        assert!(disabled.get("wallet", "aa").is_none());
+        assert!(disabled.is_empty());
+    }
+}
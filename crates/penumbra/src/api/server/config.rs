@@ -0,0 +1,468 @@
+use {
+    crate::api::server::{
+        auth::AuthConfig, limits::RequestLimits, rate_limit::RateLimit, tls::TlsConfig,
+        ServerOptions,
+    },
+    anyhow::{Context, Result},
+    camino::{Utf8Path, Utf8PathBuf},
+    serde::{Deserialize, Serialize},
+    std::time::Duration,
+};
+
+/// Node the server connects to unless configured otherwise
+pub const DEFAULT_GRPC_URL: &str = "http://localhost:8080";
+
+/// Prefix of the environment variables that override the config file
+pub const ENV_PREFIX: &str = "FARADAY_";
+
+/// Shown in place of secrets when printing the config
+const MASK: &str = "********";
+
+/// Configuration of the api server, loaded from a TOML file
+///
+/// Every key but `auth` can be overridden by an environment variable named
+/// after it, e.g. `FARADAY_LISTEN_URL` for `listen_url`. Durations are in
+/// seconds and lists are comma separated. `FARADAY_*` variables that don't
+/// name such a key are ignored, and listed in [`ServerConfig::ignored_env`].
+///
+/// ```toml
+/// grpc_url = "http://localhost:8080"
+/// listen_url = "0.0.0.0:1337"
+/// job_dir = "/var/lib/disclosure/jobs"
+///
+/// [auth]
+/// bearer = { secret = "..." }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// penumbra grpc node
+    pub grpc_url: String,
+    /// address to expose the api on
+    pub listen_url: String,
+    /// chain the node must be on for `/ready` to succeed
+    pub chain_id: Option<String>,
+    /// least severe level that is logged
+    pub log_level: String,
+    /// file request logs and everything else is appended to as JSON lines
+    pub log_file: Option<Utf8PathBuf>,
+    /// OTLP/HTTP collector spans are exported to, e.g. `http://localhost:4318/v1/traces`
+    pub otlp_endpoint: Option<String>,
+    pub client_idle_timeout: u64,
+    pub sync_concurrency: usize,
+    pub sync_interval: u64,
+    /// maximum number of syncs running at once, inline and in the background
+    pub max_concurrent_syncs: usize,
+    pub lookup_concurrency: usize,
+    /// maximum number of disclosed transactions cached, caching is disabled if 0
+    pub disclosure_cache_size: usize,
+    /// TOML file with the credentials accepted by the api, exclusive with `auth`
+    pub auth_config: Option<Utf8PathBuf>,
+    pub wallet_store: Option<Utf8PathBuf>,
+    pub wallet_key_file: Option<Utf8PathBuf>,
+    pub job_workers: usize,
+    /// most disclosure jobs queued or running at once
+    pub max_queued_jobs: usize,
+    /// directory jobs are persisted to, requires `wallet_key_file` to encrypt them
+    pub job_dir: Option<Utf8PathBuf>,
+    pub job_retention: u64,
+    pub webhook_secret_file: Option<Utf8PathBuf>,
+    pub webhook_max_attempts: u32,
+    pub webhook_initial_backoff: u64,
+    /// hosts callbacks may be delivered to even if they don't resolve to public addresses
+    pub webhook_allowed_hosts: Vec<String>,
+    /// PEM certificate chain to serve the api over TLS with, set together with `tls_key`
+    pub tls_cert: Option<Utf8PathBuf>,
+    pub tls_key: Option<Utf8PathBuf>,
+    /// PEM file with the CAs client certificates must be issued by, enables mTLS
+    pub tls_client_ca: Option<Utf8PathBuf>,
+    /// how often the TLS files are checked for changes
+    pub tls_reload_interval: u64,
+    /// origins browsers may call the api from, `*` for any
+    pub cors_allowed_origins: Vec<String>,
+    /// largest accepted request body, in bytes
+    pub max_body_size: usize,
+    pub request_timeout: u64,
+    /// most transaction hashes accepted in one batch disclosure
+    pub max_transaction_hashes: usize,
+    /// disclosure requests each caller may make per minute, unlimited if 0
+    pub rate_limit_per_minute: u32,
+    /// disclosure requests each caller may make at once
+    pub rate_limit_burst: u32,
+    /// seconds in-flight work may take to finish on shutdown before it is cancelled
+    pub shutdown_timeout: u64,
+    /// credentials accepted by the api, exclusive with `auth_config`
+    pub auth: Option<AuthConfig>,
+    /// `FARADAY_*` variables that were ignored, to warn about once logging is set up
+    #[serde(skip)]
+    pub ignored_env: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let opts = ServerOptions::default();
+        Self {
+            grpc_url: DEFAULT_GRPC_URL.to_string(),
+            listen_url: "localhost:1337".to_string(),
+            chain_id: opts.expected_chain_id,
+            log_level: "info".to_string(),
+            log_file: None,
+            otlp_endpoint: None,
+            client_idle_timeout: opts.client_idle_timeout.as_secs(),
+            sync_concurrency: opts.sync_concurrency,
+            sync_interval: opts.sync_interval.as_secs(),
+            max_concurrent_syncs: opts.max_concurrent_syncs,
+            lookup_concurrency: opts.lookup_concurrency,
+            disclosure_cache_size: opts.disclosure_cache_size,
+            auth_config: None,
+            wallet_store: opts.wallet_store,
+            wallet_key_file: opts.wallet_key_file,
+            job_workers: opts.job_workers,
+            max_queued_jobs: opts.max_queued_jobs,
+            job_dir: opts.job_dir,
+            job_retention: opts.job_retention.as_secs(),
+            webhook_secret_file: opts.webhook_secret_file,
+            webhook_max_attempts: opts.webhook_max_attempts,
+            webhook_initial_backoff: opts.webhook_initial_backoff.as_secs(),
+            webhook_allowed_hosts: opts.webhook_allowed_hosts,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            tls_reload_interval: 30,
+            cors_allowed_origins: opts.cors_allowed_origins,
+            max_body_size: opts.limits.max_body_size,
+            request_timeout: opts.limits.request_timeout.as_secs(),
+            max_transaction_hashes: opts.limits.max_transaction_hashes,
+            rate_limit_per_minute: 0,
+            rate_limit_burst: 10,
+            shutdown_timeout: opts.shutdown_timeout.as_secs(),
+            auth: opts.auth,
+            ignored_env: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Loads the config from `path`, if given, and applies the environment overrides
+    pub fn load(path: Option<&Utf8Path>) -> Result<Self> {
+        let mut cfg = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {path}"))?;
+                toml::from_str(&contents).with_context(|| format!("failed to parse {path}"))?
+            }
+            None => Self::default(),
+        };
+        cfg.apply_env(std::env::vars())?;
+        Ok(cfg)
+    }
+
+    /// Overrides keys with the `FARADAY_*` variables in `vars`
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<()> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if !self
+                .set(&key.to_lowercase(), &value)
+                .with_context(|| format!("invalid {name}"))?
+            {
+                self.ignored_env.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets `key` to `value`, returning false if `key` can't be set from the environment
+    fn set(&mut self, key: &str, value: &str) -> Result<bool> {
+        let path = || Some(Utf8PathBuf::from(value));
+        let list = || {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        match key {
+            "grpc_url" => self.grpc_url = value.to_string(),
+            "listen_url" => self.listen_url = value.to_string(),
+            "chain_id" => self.chain_id = Some(value.to_string()),
+            "log_level" => self.log_level = value.to_string(),
+            "log_file" => self.log_file = path(),
+            "otlp_endpoint" => self.otlp_endpoint = Some(value.to_string()),
+            "client_idle_timeout" => self.client_idle_timeout = value.parse()?,
+            "sync_concurrency" => self.sync_concurrency = value.parse()?,
+            "sync_interval" => self.sync_interval = value.parse()?,
+            "max_concurrent_syncs" => self.max_concurrent_syncs = value.parse()?,
+            "lookup_concurrency" => self.lookup_concurrency = value.parse()?,
+            "disclosure_cache_size" => self.disclosure_cache_size = value.parse()?,
+            "auth_config" => self.auth_config = path(),
+            "wallet_store" => self.wallet_store = path(),
+            "wallet_key_file" => self.wallet_key_file = path(),
+            "job_workers" => self.job_workers = value.parse()?,
+            "max_queued_jobs" => self.max_queued_jobs = value.parse()?,
+            "job_dir" => self.job_dir = path(),
+            "job_retention" => self.job_retention = value.parse()?,
+            "webhook_secret_file" => self.webhook_secret_file = path(),
+            "webhook_max_attempts" => self.webhook_max_attempts = value.parse()?,
+            "webhook_initial_backoff" => self.webhook_initial_backoff = value.parse()?,
+            "webhook_allowed_hosts" => self.webhook_allowed_hosts = list(),
+            "tls_cert" => self.tls_cert = path(),
+            "tls_key" => self.tls_key = path(),
+            "tls_client_ca" => self.tls_client_ca = path(),
+            "tls_reload_interval" => self.tls_reload_interval = value.parse()?,
+            "cors_allowed_origins" => self.cors_allowed_origins = list(),
+            "max_body_size" => self.max_body_size = value.parse()?,
+            "request_timeout" => self.request_timeout = value.parse()?,
+            "max_transaction_hashes" => self.max_transaction_hashes = value.parse()?,
+            "rate_limit_per_minute" => self.rate_limit_per_minute = value.parse()?,
+            "rate_limit_burst" => self.rate_limit_burst = value.parse()?,
+            "shutdown_timeout" => self.shutdown_timeout = value.parse()?,
+            // `auth` is a table, so it can only be set in the config file
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Checks the config is consistent, without touching any of the files it refers to
+    pub fn validate(&self) -> Result<()> {
+        self.grpc_url
+            .parse::<http::Uri>()
+            .with_context(|| format!("invalid grpc_url {}", self.grpc_url))?;
+        if self.listen_url.is_empty() {
+            anyhow::bail!("listen_url must be set");
+        }
+        self.log_level
+            .parse::<tracing::Level>()
+            .with_context(|| format!("invalid log_level {}", self.log_level))?;
+        if let Some(endpoint) = &self.otlp_endpoint {
+            endpoint
+                .parse::<http::Uri>()
+                .ok()
+                .filter(|uri| uri.scheme().is_some())
+                .with_context(|| format!("invalid otlp_endpoint {endpoint}"))?;
+        }
+        for (key, value) in [
+            ("sync_concurrency", self.sync_concurrency),
+            ("max_concurrent_syncs", self.max_concurrent_syncs),
+            ("lookup_concurrency", self.lookup_concurrency),
+            ("job_workers", self.job_workers),
+            ("max_queued_jobs", self.max_queued_jobs),
+            ("webhook_max_attempts", self.webhook_max_attempts as usize),
+            ("max_body_size", self.max_body_size),
+            ("request_timeout", self.request_timeout as usize),
+            ("max_transaction_hashes", self.max_transaction_hashes),
+            ("rate_limit_burst", self.rate_limit_burst as usize),
+        ] {
+            if value == 0 {
+                anyhow::bail!("{key} must be at least 1");
+            }
+        }
+        if self.wallet_store.is_some() && self.wallet_key_file.is_none() {
+            anyhow::bail!("wallet_store requires wallet_key_file");
+        }
+        if self.job_dir.is_some() && self.wallet_key_file.is_none() {
+            anyhow::bail!("job_dir requires wallet_key_file to encrypt persisted jobs");
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            anyhow::bail!("tls_cert and tls_key must be set together");
+        }
+        if self.tls_client_ca.is_some() && self.tls_cert.is_none() {
+            anyhow::bail!("tls_client_ca requires tls_cert and tls_key");
+        }
+        for origin in &self.cors_allowed_origins {
+            if origin == "*" && self.cors_allowed_origins.len() > 1 {
+                anyhow::bail!("cors_allowed_origins can't mix * with other origins");
+            }
+            if origin != "*" {
+                origin
+                    .parse::<http::Uri>()
+                    .ok()
+                    .filter(|uri| uri.scheme().is_some() && uri.host().is_some())
+                    .and_then(|_| http::HeaderValue::from_str(origin).ok())
+                    .with_context(|| format!("invalid cors origin {origin}"))?;
+            }
+        }
+        match (&self.auth, &self.auth_config) {
+            (Some(_), Some(_)) => anyhow::bail!("only one of auth and auth_config may be set"),
+            (Some(auth), None) => auth.validate()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Validates the config and resolves it into the options the server runs with
+    pub fn options(&self) -> Result<ServerOptions> {
+        self.validate()?;
+        let auth = match &self.auth_config {
+            Some(path) => Some(AuthConfig::load(path)?),
+            None => self.auth.clone(),
+        };
+        Ok(ServerOptions {
+            client_idle_timeout: Duration::from_secs(self.client_idle_timeout),
+            sync_concurrency: self.sync_concurrency,
+            sync_interval: Duration::from_secs(self.sync_interval),
+            max_concurrent_syncs: self.max_concurrent_syncs,
+            lookup_concurrency: self.lookup_concurrency,
+            disclosure_cache_size: self.disclosure_cache_size,
+            auth,
+            rate_limit: (self.rate_limit_per_minute > 0).then_some(RateLimit {
+                per_minute: self.rate_limit_per_minute,
+                burst: self.rate_limit_burst,
+            }),
+            wallet_store: self.wallet_store.clone(),
+            wallet_key_file: self.wallet_key_file.clone(),
+            job_workers: self.job_workers,
+            max_queued_jobs: self.max_queued_jobs,
+            job_dir: self.job_dir.clone(),
+            job_retention: Duration::from_secs(self.job_retention),
+            webhook_secret_file: self.webhook_secret_file.clone(),
+            webhook_max_attempts: self.webhook_max_attempts,
+            webhook_initial_backoff: Duration::from_secs(self.webhook_initial_backoff),
+            webhook_allowed_hosts: self.webhook_allowed_hosts.clone(),
+            expected_chain_id: self.chain_id.clone(),
+            tls: match (&self.tls_cert, &self.tls_key) {
+                (Some(cert), Some(key)) => Some(TlsConfig {
+                    cert: cert.clone(),
+                    key: key.clone(),
+                    client_ca: self.tls_client_ca.clone(),
+                    reload_interval: Duration::from_secs(self.tls_reload_interval),
+                }),
+                _ => None,
+            },
+            limits: RequestLimits {
+                max_body_size: self.max_body_size,
+                request_timeout: Duration::from_secs(self.request_timeout),
+                max_transaction_hashes: self.max_transaction_hashes,
+            },
+            cors_allowed_origins: self.cors_allowed_origins.clone(),
+            shutdown_timeout: Duration::from_secs(self.shutdown_timeout),
+        })
+    }
+
+    /// Renders the config as TOML, with every secret masked
+    pub fn to_masked_toml(&self) -> Result<String> {
+        let mut cfg = self.clone();
+        if let Some(auth) = &mut cfg.auth {
+            for api_key in &mut auth.api_keys {
+                api_key.key = MASK.to_string();
+            }
+            if let Some(bearer) = &mut auth.bearer {
+                bearer.secret = MASK.to_string();
+            }
+        }
+        toml::to_string_pretty(&cfg).with_context(|| "failed to render config")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_server_config() {
+        let mut cfg: ServerConfig = toml::from_str(
+            r#"
+            listen_url = "0.0.0.0:1337"
+            job_workers = 8
+
+            [[auth.api_keys]]
+            name = "ops"
+            key = "super-secret-api-key"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.job_workers, 8);
+        assert_eq!(cfg.grpc_url, ServerConfig::default().grpc_url);
+
+        cfg.apply_env([
+            ("FARADAY_JOB_WORKERS".to_string(), "2".to_string()),
+            ("FARADAY_JOB_DIR".to_string(), "/tmp/jobs".to_string()),
+            (
+                "FARADAY_WALLET_KEY_FILE".to_string(),
+                "/tmp/wallets.key".to_string(),
+            ),
+            ("FARADAY_MAX_QUEUED_JOBS".to_string(), "10".to_string()),
+            (
+                "FARADAY_WEBHOOK_ALLOWED_HOSTS".to_string(),
+                "hooks.internal, 10.0.0.5".to_string(),
+            ),
+            ("FARADAY_SHUTDOWN_TIMEOUT".to_string(), "5".to_string()),
+            ("FARADAY_DISCLOSURE_CACHE_SIZE".to_string(), "0".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ])
+        .unwrap();
+        assert_eq!(cfg.job_workers, 2);
+        assert_eq!(cfg.job_dir, Some(Utf8PathBuf::from("/tmp/jobs")));
+        // unknown keys are only warned about, so other tools sharing the prefix don't break startup
+        let mut ignored = cfg.clone();
+        ignored
+            .apply_env([
+                ("FARADAY_JOB_WORKRS".to_string(), "2".to_string()),
+                ("FARADAY_AUTH".to_string(), "{}".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(ignored.ignored_env, ["FARADAY_JOB_WORKRS", "FARADAY_AUTH"]);
+        assert_eq!(ignored.job_workers, cfg.job_workers);
+        assert!(cfg
+            .clone()
+            .apply_env([("FARADAY_JOB_WORKERS".to_string(), "many".to_string())])
+            .is_err());
+
+        let opts = cfg.options().unwrap();
+        assert_eq!(opts.job_workers, 2);
+        assert_eq!(opts.max_queued_jobs, 10);
+        assert_eq!(opts.webhook_allowed_hosts, ["hooks.internal", "10.0.0.5"]);
+        assert_eq!(opts.shutdown_timeout, Duration::from_secs(5));
+        assert_eq!(opts.disclosure_cache_size, 0);
+        let mut loud = cfg.clone();
+        loud.apply_env([("FARADAY_LOG_LEVEL".to_string(), "loud".to_string())])
+            .unwrap();
+        assert!(loud.validate().is_err());
+        let mut otlp = cfg.clone();
+        otlp.apply_env([(
+            "FARADAY_OTLP_ENDPOINT".to_string(),
+            "http://localhost:4318/v1/traces".to_string(),
+        )])
+        .unwrap();
+        assert!(otlp.validate().is_ok());
+        otlp.otlp_endpoint = Some("localhost".to_string());
+        assert!(otlp.validate().is_err());
+        assert_eq!(opts.auth.unwrap().api_keys[0].name, "ops");
+
+        let masked = cfg.to_masked_toml().unwrap();
+        assert!(!masked.contains("super-secret-api-key"));
+        assert!(masked.contains(MASK));
+        let masked: ServerConfig = toml::from_str(&masked).unwrap();
+        assert_eq!(masked.job_dir, cfg.job_dir);
+
+        cfg.apply_env([(
+            "FARADAY_CORS_ALLOWED_ORIGINS".to_string(),
+            "https://app.example.com, http://localhost:3000".to_string(),
+        )])
+        .unwrap();
+        assert_eq!(
+            cfg.options().unwrap().cors_allowed_origins,
+            ["https://app.example.com", "http://localhost:3000"]
+        );
+        for origins in [
+            vec!["*", "https://app.example.com"],
+            vec!["app.example.com"],
+        ] {
+            let mut cfg = cfg.clone();
+            cfg.cors_allowed_origins = origins.into_iter().map(str::to_string).collect();
+            assert!(cfg.validate().is_err());
+        }
+
+        cfg.wallet_store = Some(Utf8PathBuf::from("wallets.json"));
+        assert!(cfg.validate().is_ok());
+        // both the wallet store and persisted jobs are encrypted under the wallet key
+        cfg.wallet_key_file = None;
+        assert!(cfg.validate().is_err());
+        cfg.wallet_store = None;
+        assert!(cfg.validate().is_err());
+        cfg.job_dir = None;
+        assert!(cfg.validate().is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+use {
+    crate::{
+        api::server::{auth::AuthError, request_log},
+        error::{redact_fvks, Error},
+    },
+    axum::{
+        response::{IntoResponse, Response},
+        Json,
+    },
+    common::models::{disclosure_error::ResultType, error::Error as CommonError, DisclosureError},
+    http::{header, HeaderValue, StatusCode},
+    std::time::Duration,
+};
+
+/// Suggested wait before retrying a request whose wallet sync was queued
+const SYNC_QUEUED_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Suggested wait before resubmitting a job rejected because the queue was full
+const JOB_QUEUE_FULL_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Errors the api fails requests with: disclosure errors, and those of the
+/// api itself that [`DisclosureClient`] users never see
+///
+/// Every error maps to a machine-readable identifier, see [`ApiError::error_type`],
+/// sent as the `code` of the error response.
+///
+/// [`DisclosureClient`]: crate::client::DisclosureClient
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error(transparent)]
+    Disclosure(#[from] Error),
+    #[error(transparent)]
+    Unauthorized(#[from] AuthError),
+    #[error("the disclosure matches If-None-Match")]
+    PreconditionFailed,
+    #[error("request body exceeds {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("request timed out")]
+    RequestTimeout,
+    #[error("at most {0} transaction hashes may be disclosed per request")]
+    TooManyTransactions(usize),
+    #[error("rate limit exceeded")]
+    RateLimited(Duration),
+    #[error("the wallet's sync is queued behind other syncs, retry later")]
+    SyncQueued,
+    #[error("at most {0} disclosure jobs may be queued, retry later")]
+    JobQueueFull(usize),
+}
+
+impl ApiError {
+    /// Machine-readable identifier of the error
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            Self::Disclosure(err) => err.error_type(),
+            Self::Unauthorized(..) => "unauthorized",
+            Self::PreconditionFailed => "precondition_failed",
+            Self::PayloadTooLarge(..) => "payload_too_large",
+            Self::RequestTimeout => "request_timeout",
+            Self::TooManyTransactions(..) => "too_many_transactions",
+            Self::RateLimited(..) => "rate_limited",
+            Self::SyncQueued => "sync_queued",
+            Self::JobQueueFull(..) => "job_queue_full",
+        }
+    }
+
+    /// HTTP status returned when the error fails a whole request
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Disclosure(err) => err.status(),
+            Self::Unauthorized(..) => StatusCode::UNAUTHORIZED,
+            Self::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Self::PayloadTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
+            Self::TooManyTransactions(..) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::RateLimited(..) => StatusCode::TOO_MANY_REQUESTS,
+            Self::SyncQueued | Self::JobQueueFull(..) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// How long the client should wait before retrying, sent as `Retry-After`
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited(retry_after) => Some(*retry_after),
+            Self::SyncQueued => Some(SYNC_QUEUED_RETRY_AFTER),
+            Self::JobQueueFull(..) => Some(JOB_QUEUE_FULL_RETRY_AFTER),
+            _ => None,
+        }
+    }
+
+    /// Formats the error along with its sources for server side logs, see [`Error::details`]
+    pub fn details(&self) -> String {
+        match self {
+            Self::Disclosure(err) => err.details(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl Error {
+    /// HTTP status returned when the error fails a whole request
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::TransactionNotFound | Self::WalletNotFound | Self::JobNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            Self::InvalidRequest(..) | Self::InvalidFvk(..) | Self::InvalidHash(..) => {
+                StatusCode::BAD_REQUEST
+            }
+            // failures of the node, missing asset metadata included, rather than of the request
+            Self::SyncFailed(..) | Self::UnknownAsset(..) => StatusCode::BAD_GATEWAY,
+            Self::NodeUnavailable(..) | Self::Interrupted | Self::Cancelled => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::Internal(..) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Per-transaction error entry used in batch disclosure results
+    ///
+    /// The hash may be malformed client input, so it's redacted like the error's details.
+    pub fn disclosure_error(&self, transaction_hash: String) -> DisclosureError {
+        let transaction_hash = redact_fvks(&transaction_hash);
+        log::warn!(
+            "failed to disclose transaction {transaction_hash}: {}",
+            self.details()
+        );
+        DisclosureError {
+            result_type: ResultType::Error,
+            transaction_hash,
+            error: self.into(),
+            error_types: vec![self.error_type().to_string()],
+        }
+    }
+}
+
+impl From<&Error> for CommonError {
+    fn from(err: &Error) -> Self {
+        CommonError {
+            code: err.error_type().to_string(),
+            // the sources are only ever logged, they may contain node internals
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<&ApiError> for CommonError {
+    fn from(err: &ApiError) -> Self {
+        CommonError {
+            code: err.error_type().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        request_log::record_error(self.error_type());
+        if status.is_server_error() {
+            log::error!("request failed with {status}: {}", self.details());
+        } else {
+            log::warn!("request failed with {status}: {}", self.details());
+        }
+        let mut res = (status, Json(CommonError::from(&self))).into_response();
+        if let Some(retry_after) = self.retry_after() {
+            // round up so clients never retry too early
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, secs.max(1).into());
+        }
+        if let Self::Unauthorized(..) = self {
+            res.headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        res
+    }
+}
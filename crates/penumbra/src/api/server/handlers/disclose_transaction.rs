@@ -1,87 +1,97 @@
 use {
-    crate::{api::server::router::AppState, client::DisclosureClient},
-    axum::{extract::State, response::IntoResponse, Json},
-    common::{
-        apis::default_api::DiscloseSingleTransactionError,
-        models::{
-            error::Error as CommonError, DisclosedTransactionResult,
-            DisclosedTransactionResultDisclosureTransactions, DisclosureRequestSingle,
+    crate::{
+        api::server::{
+            cache::CachedDisclosure,
+            error::ApiError,
+            handlers::{json_with_etag, request_fvk, WALLET_ID_HEADER},
+            request_log,
+            router::AppState,
         },
+        client::{fvk_hash, normalize_hash},
+        error::Error,
     },
+    axum::{
+        extract::{Path, State},
+        response::Response,
+        Json,
+    },
+    common::models::DisclosureRequestSingle,
+    http::{header, HeaderMap, HeaderValue, Method},
     penumbra_sdk_keys::FullViewingKey,
-    reqwest::StatusCode,
-    std::{str::FromStr, sync::Arc},
+    std::sync::Arc,
 };
 
+/// Discloses a single transaction, from the cache if it was disclosed before
 pub async fn disclose_transaction(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<DisclosureRequestSingle>,
-) -> impl IntoResponse {
-    let state = state.clone();
-    let fvk = match FullViewingKey::from_str(&payload.full_viewing_key) {
-        Ok(fvk) => fvk,
-        Err(err) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(DiscloseSingleTransactionError::Status400(CommonError {
-                    code: StatusCode::BAD_REQUEST.to_string(),
-                    message: format!("{err:#?}"),
-                })),
-            )
-                .into_response()
-        }
-    };
-    let dc = match DisclosureClient::new(&state.url, &fvk).await {
-        Ok(dc) => dc,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DiscloseSingleTransactionError::Status500(CommonError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                    message: format!("failed to initialize disclosure client {err:#?}"),
-                })),
-            )
-                .into_response()
-        }
-    };
+) -> Result<Response, ApiError> {
+    request_log::record_transactions(1);
+    let transaction_hash = normalize_hash(&payload.transaction_hash)?;
+    let fvk = request_fvk(
+        &state,
+        payload.full_viewing_key.as_deref(),
+        payload.wallet_id.as_deref(),
+    )
+    .await?;
+    let disclosure = disclose(&state, &fvk, &transaction_hash).await?;
 
-    {
-        let dc = dc.lock().await;
+    json_with_etag(
+        &Method::POST,
+        &headers,
+        Some(disclosure.synced_height),
+        &disclosure.transaction,
+    )
+}
 
-        if let Err(err) = dc.sync().await {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DiscloseSingleTransactionError::Status500(CommonError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                    message: format!("failed to synchronize disclosure client {err:#?}"),
-                })),
-            )
-                .into_response();
-        }
-    }
+/// Discloses a single transaction of a registered wallet, the cacheable
+/// counterpart of [`disclose_transaction`]
+///
+/// Handles grant the same access as the FVK, so the handle is taken from the
+/// `X-Wallet-Id` header instead of the URL, and responses vary on it.
+pub async fn get_disclosed_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(transaction_hash): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    request_log::record_transactions(1);
+    let transaction_hash = normalize_hash(&transaction_hash)?;
+    let wallet_id = headers
+        .get(WALLET_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Error::InvalidRequest("X-Wallet-Id is required".to_string()))?;
+    let fvk = request_fvk(&state, None, Some(wallet_id)).await?;
+    let disclosure = disclose(&state, &fvk, &transaction_hash).await?;
+
+    let mut res = json_with_etag(
+        &Method::GET,
+        &headers,
+        Some(disclosure.synced_height),
+        &disclosure.transaction,
+    )?;
+    res.headers_mut()
+        .insert(header::VARY, HeaderValue::from_static(WALLET_ID_HEADER));
+    Ok(res)
+}
 
-    let txn = {
-        let dc = dc.lock().await;
-        dc.transaction(&payload.transaction_hash).await
-    };
-    match txn {
-        Ok(tx_info) => (
-            StatusCode::OK,
-            Json(DisclosedTransactionResult {
-                disclosure_transactions: Some(DisclosedTransactionResultDisclosureTransactions {
-                    transactions: vec![tx_info],
-                }),
-                disclosure_errors: None,
-            }),
-        )
-            .into_response(),
-        Err(err) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(DiscloseSingleTransactionError::Status500(CommonError {
-                code: StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                message: format!("failed to generate disclosure bundle {err:#?}"),
-            })),
-        )
-            .into_response(),
+/// Serves a disclosure from the cache, only syncing the wallet if it isn't cached
+async fn disclose(
+    state: &AppState,
+    fvk: &FullViewingKey,
+    transaction_hash: &str,
+) -> Result<CachedDisclosure, ApiError> {
+    let wallet = fvk_hash(fvk);
+    if let Some(disclosure) = state.cache.get(&wallet, transaction_hash) {
+        return Ok(disclosure);
     }
+    let (dc, synced_height) = state.sync.client(fvk).await?;
+    let transaction = dc.transaction(transaction_hash).await?;
+    state
+        .cache
+        .insert(&wallet, transaction_hash, &transaction, synced_height);
+    Ok(CachedDisclosure {
+        transaction,
+        synced_height,
+    })
 }
@@ -1,85 +1,74 @@
 use {
-    crate::{api::server::router::AppState, client::DisclosureClient},
-    axum::{extract::State, response::IntoResponse, Json},
-    common::{
-        apis::default_api::DiscloseMultipleTransactionsError,
-        models::{
-            disclosure_error::ResultType, error::Error as CommonError, DisclosedTransactionResult,
-            DisclosedTransactionResultDisclosureErrors,
-            DisclosedTransactionResultDisclosureTransactions, DisclosureError,
-            DisclosureRequestMultiple, Transaction,
+    crate::{
+        api::server::{
+            error::ApiError,
+            handlers::{disclose_cached, json_with_etag, normalize_hashes, request_fvk},
+            request_log,
+            router::AppState,
         },
+        error::Error,
     },
-    penumbra_sdk_keys::FullViewingKey,
-    reqwest::StatusCode,
-    std::{str::FromStr, sync::Arc},
+    axum::{extract::State, response::Response, Json},
+    common::models::{
+        DisclosedTransactionResult, DisclosedTransactionResultDisclosureErrors,
+        DisclosedTransactionResultDisclosureTransactions, DisclosureError,
+        DisclosureRequestMultiple, Transaction,
+    },
+    http::{HeaderMap, Method},
+    std::sync::Arc,
 };
 
+/// Discloses a batch of transactions
+///
+/// The wallet is only synced if at least one of the hashes is well formed and
+/// wasn't disclosed before.
 pub async fn disclose_transactions(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<DisclosureRequestMultiple>,
-) -> impl IntoResponse {
-    let state = state.clone();
-    let fvk = match FullViewingKey::from_str(&payload.full_viewing_key) {
-        Ok(fvk) => fvk,
-        Err(err) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(DiscloseMultipleTransactionsError::Status400(CommonError {
-                    code: StatusCode::BAD_REQUEST.to_string(),
-                    message: format!("{err:#?}"),
-                })),
-            )
-                .into_response()
-        }
-    };
-    let dc = match DisclosureClient::new(&state.url, &fvk).await {
-        Ok(dc) => dc,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DiscloseMultipleTransactionsError::Status500(CommonError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                    message: format!("failed to initialize disclosure client {err:#?}"),
-                })),
-            )
-                .into_response()
-        }
-    };
-
-    {
-        let dc = dc.lock().await;
-
-        if let Err(err) = dc.sync().await {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DiscloseMultipleTransactionsError::Status500(CommonError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                    message: format!("failed to synchronize disclosure client {err:#?}"),
-                })),
-            )
-                .into_response();
-        }
+) -> Result<Response, ApiError> {
+    if payload.callback_url.is_some() {
+        return Err(Error::InvalidRequest(
+            "callbackUrl is only supported by POST /jobs/disclose".to_string(),
+        )
+        .into());
     }
+    request_log::record_transactions(payload.transaction_hashes.len());
+    state.limits.check_batch(&payload.transaction_hashes)?;
+    let (transaction_hashes, invalid) = normalize_hashes(payload.transaction_hashes);
+    let fvk = request_fvk(
+        &state,
+        payload.full_viewing_key.as_deref(),
+        payload.wallet_id.as_deref(),
+    )
+    .await?;
+    let (results, synced_height) = disclose_cached(&state, &fvk, &transaction_hashes, || {
+        state.sync.client(&fvk)
+    })
+    .await?;
 
-    let mut disclosed_transactions: Vec<Transaction> =
-        Vec::with_capacity(payload.transaction_hashes.len());
-    let mut disclosure_errors: Vec<DisclosureError> =
-        Vec::with_capacity(payload.transaction_hashes.len());
+    json_with_etag(
+        &Method::POST,
+        &headers,
+        synced_height,
+        &disclosure_result(transaction_hashes, results, invalid),
+    )
+}
 
-    for tx_hash in payload.transaction_hashes {
-        let dc = dc.lock().await;
-        match dc.transaction(&tx_hash).await {
+/// Splits the results of a batch disclosure into disclosed transactions and errors,
+/// which start with the errors of the hashes that were never looked up
+pub(crate) fn disclosure_result(
+    transaction_hashes: Vec<String>,
+    results: Vec<Result<Transaction, Error>>,
+    mut disclosure_errors: Vec<DisclosureError>,
+) -> DisclosedTransactionResult {
+    let mut disclosed_transactions: Vec<Transaction> = Vec::with_capacity(transaction_hashes.len());
+    disclosure_errors.reserve(transaction_hashes.len());
+
+    for (tx_hash, result) in transaction_hashes.into_iter().zip(results) {
+        match result {
             Ok(tx_info) => disclosed_transactions.push(tx_info),
-            Err(err) => disclosure_errors.push(DisclosureError {
-                result_type: ResultType::Error,
-                transaction_hash: tx_hash,
-                error: CommonError {
-                    code: StatusCode::BAD_REQUEST.to_string(),
-                    message: format!("{err:#?}"),
-                },
-                error_types: vec![],
-            }),
+            Err(err) => disclosure_errors.push(err.disclosure_error(tx_hash)),
         }
     }
     let disclosure_errors = if disclosure_errors.is_empty() {
@@ -97,12 +86,8 @@ pub async fn disclose_transactions(
         })
     };
 
-    (
-        StatusCode::OK,
-        Json(DisclosedTransactionResult {
-            disclosure_errors,
-            disclosure_transactions,
-        }),
-    )
-        .into_response()
+    DisclosedTransactionResult {
+        disclosure_errors,
+        disclosure_transactions,
+    }
 }
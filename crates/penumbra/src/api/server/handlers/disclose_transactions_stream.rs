@@ -0,0 +1,192 @@
+use {
+    crate::{
+        api::server::{
+            error::ApiError,
+            handlers::{normalize_hashes, request_fvk},
+            request_log,
+            router::AppState,
+            sync::SyncProgress,
+        },
+        client::fvk_hash,
+        error::Error,
+    },
+    axum::{
+        extract::State,
+        response::sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+    common::models::{
+        error::Error as CommonError, DisclosureError, DisclosureRequestMultiple,
+        DisclosureStreamProgress, DisclosureStreamSummary,
+    },
+    futures::{channel::mpsc, Stream, StreamExt},
+    penumbra_sdk_keys::FullViewingKey,
+    serde::Serialize,
+    std::sync::Arc,
+    tokio_util::sync::CancellationToken,
+    tracing::Instrument,
+};
+
+/// Event sent when the wallet's sync waits for a free sync slot, an empty object
+pub const QUEUED_EVENT: &str = "queued";
+/// Event reporting the progress of the wallet's sync, a [`DisclosureStreamProgress`]
+pub const PROGRESS_EVENT: &str = "progress";
+/// Event carrying a disclosed [`common::models::Transaction`]
+pub const TRANSACTION_EVENT: &str = "transaction";
+/// Event carrying the [`common::models::DisclosureError`] of a transaction that failed to disclose
+pub const DISCLOSURE_ERROR_EVENT: &str = "disclosureError";
+/// Final event when the whole disclosure failed, a [`CommonError`]
+pub const ERROR_EVENT: &str = "error";
+/// Final event once every transaction was disclosed or failed, a [`DisclosureStreamSummary`]
+pub const DONE_EVENT: &str = "done";
+
+type EventSender = mpsc::UnboundedSender<Result<Event, axum::Error>>;
+
+/// Streams a batch disclosure as server-sent events
+///
+/// Sync progress is reported while the wallet's view catches up, after a
+/// `queued` event if it has to wait for a sync slot. Then one event is sent
+/// per transaction as soon as it is disclosed, in completion order. Malformed
+/// hashes and cached disclosures are reported before the sync starts, and the
+/// wallet isn't synced at all if every disclosure is cached.
+///
+/// The sync and lookups are cancelled as soon as the client disconnects.
+pub async fn disclose_transactions_stream(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DisclosureRequestMultiple>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    if payload.callback_url.is_some() {
+        return Err(Error::InvalidRequest(
+            "callbackUrl is only supported by POST /jobs/disclose".to_string(),
+        )
+        .into());
+    }
+    request_log::record_transactions(payload.transaction_hashes.len());
+    state.limits.check_batch(&payload.transaction_hashes)?;
+    let (transaction_hashes, invalid) = normalize_hashes(payload.transaction_hashes);
+    let fvk = request_fvk(
+        &state,
+        payload.full_viewing_key.as_deref(),
+        payload.wallet_id.as_deref(),
+    )
+    .await?;
+
+    let (events, rx) = mpsc::unbounded();
+    let cancel = state.shutdown.token();
+    // the response body owns the guard, so dropping it once the client goes
+    // away cancels the disclosure
+    let guard = cancel.clone().drop_guard();
+    let rx = rx.map(move |event| {
+        let _guard = &guard;
+        event
+    });
+    // the stream outlives the handler, but still belongs to the request's trace
+    state.shutdown.spawn(
+        stream_disclosures(
+            state.clone(),
+            fvk,
+            transaction_hashes,
+            invalid,
+            cancel,
+            events,
+        )
+        .in_current_span(),
+    );
+    Ok(Sse::new(rx).keep_alive(KeepAlive::default()))
+}
+
+async fn stream_disclosures(
+    state: Arc<AppState>,
+    fvk: FullViewingKey,
+    transaction_hashes: Vec<String>,
+    invalid: Vec<DisclosureError>,
+    cancel: CancellationToken,
+    events: EventSender,
+) {
+    let mut summary = DisclosureStreamSummary::new(0, 0, invalid.len() as u32);
+    for err in invalid {
+        let _ = events.unbounded_send(event(DISCLOSURE_ERROR_EVENT, &err));
+    }
+    let wallet = fvk_hash(&fvk);
+    let mut missing = Vec::new();
+    for tx_hash in transaction_hashes {
+        match state.cache.get(&wallet, &tx_hash) {
+            Some(disclosure) => {
+                summary.disclosed += 1;
+                summary.synced_height = summary.synced_height.max(disclosure.synced_height);
+                let _ = events.unbounded_send(event(TRANSACTION_EVENT, &disclosure.transaction));
+            }
+            None => missing.push(tx_hash),
+        }
+    }
+    // every disclosure was cached or malformed, so there's nothing to sync for
+    if missing.is_empty() {
+        let _ = events.unbounded_send(event(DONE_EVENT, &summary));
+        return;
+    }
+
+    let synced = state
+        .sync
+        .client_with_progress(&fvk, cancel, |progress| {
+            let next = match progress {
+                SyncProgress::Queued => event(QUEUED_EVENT, &serde_json::json!({})),
+                SyncProgress::Syncing {
+                    synced_height,
+                    latest_height,
+                } => event(
+                    PROGRESS_EVENT,
+                    &DisclosureStreamProgress {
+                        synced_height,
+                        latest_height,
+                    },
+                ),
+            };
+            let _ = events.unbounded_send(next);
+        })
+        .await;
+    let (dc, synced_height) = match synced {
+        Ok(synced) => synced,
+        Err(err) => {
+            log::warn!("streamed disclosure failed: {}", err.details());
+            let _ = events.unbounded_send(event(ERROR_EVENT, &CommonError::from(&err)));
+            return;
+        }
+    };
+
+    summary.synced_height = synced_height;
+    let mut lookups = dc.transactions_stream(&missing, state.lookup_concurrency);
+    while let Some((index, result)) = lookups.next().await {
+        let tx_hash = &missing[index];
+        let next = match result {
+            // the server is shutting down, or the client went away
+            Err(Error::Cancelled) => {
+                let _ = events
+                    .unbounded_send(event(ERROR_EVENT, &CommonError::from(&Error::Cancelled)));
+                return;
+            }
+            Ok(tx_info) => {
+                summary.disclosed += 1;
+                state
+                    .cache
+                    .insert(&wallet, tx_hash, &tx_info, synced_height);
+                event(TRANSACTION_EVENT, &tx_info)
+            }
+            Err(err) => {
+                summary.failed += 1;
+                event(
+                    DISCLOSURE_ERROR_EVENT,
+                    &err.disclosure_error(tx_hash.clone()),
+                )
+            }
+        };
+        // the client went away, so there's no one left to disclose to
+        if events.unbounded_send(next).is_err() {
+            return;
+        }
+    }
+    let _ = events.unbounded_send(event(DONE_EVENT, &summary));
+}
+
+fn event(name: &str, data: &impl Serialize) -> Result<Event, axum::Error> {
+    Event::default().event(name).json_data(data)
+}
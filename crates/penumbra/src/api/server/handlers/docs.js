@@ -0,0 +1,229 @@
+// Renders openapi.json and lets operations be tried out against this server.
+// Served from the binary so the docs don't load any third party code.
+"use strict";
+
+const METHODS = ["get", "post", "put", "patch", "delete"];
+
+function el(tag, attrs, ...children) {
+  const node = document.createElement(tag);
+  for (const [name, value] of Object.entries(attrs || {})) {
+    node.setAttribute(name, value);
+  }
+  for (const child of children) {
+    if (child !== undefined && child !== null) {
+      node.append(child);
+    }
+  }
+  return node;
+}
+
+function resolve(spec, value) {
+  if (!value || !value.$ref) {
+    return value;
+  }
+  return value.$ref
+    .slice(2)
+    .split("/")
+    .reduce((node, key) => node[key], spec);
+}
+
+function refName(schema) {
+  return schema && schema.$ref ? schema.$ref.split("/").pop() : null;
+}
+
+function typeOf(schema) {
+  const name = refName(schema);
+  if (name) {
+    return el("a", { href: `#schema-${name}` }, name);
+  }
+  if (!schema) {
+    return "";
+  }
+  if (schema.type === "array") {
+    const items = typeOf(schema.items);
+    return el("span", {}, "array of ", items);
+  }
+  let type = [].concat(schema.type || "object").join(" | ");
+  if (schema.format) {
+    type += ` (${schema.format})`;
+  }
+  if (schema.enum) {
+    type += `: ${schema.enum.join(", ")}`;
+  }
+  return type;
+}
+
+// Builds an example request body from the schema's examples and required properties
+function example(spec, schema, depth = 0) {
+  schema = resolve(spec, schema);
+  if (!schema || depth > 4) {
+    return null;
+  }
+  if (schema.examples) {
+    return schema.examples[0];
+  }
+  if (schema.enum) {
+    return schema.enum[0];
+  }
+  switch ([].concat(schema.type)[0]) {
+    case "array":
+      return [example(spec, schema.items, depth + 1)];
+    case "string":
+      return "";
+    case "integer":
+    case "number":
+      return 0;
+    case "boolean":
+      return false;
+  }
+  const value = {};
+  for (const name of schema.required || []) {
+    value[name] = example(spec, schema.properties[name], depth + 1);
+  }
+  return value;
+}
+
+function table(headings, rows) {
+  return el(
+    "table",
+    {},
+    el("tr", {}, ...headings.map((heading) => el("th", {}, heading))),
+    ...rows.map((row) => el("tr", {}, ...row.map((cell) => el("td", {}, cell)))),
+  );
+}
+
+async function send(path, method, inputs, body, output) {
+  let uri = path;
+  for (const [name, input] of Object.entries(inputs.path)) {
+    uri = uri.replace(`{${name}}`, encodeURIComponent(input.value));
+  }
+  const headers = {};
+  for (const [name, input] of Object.entries(inputs.header)) {
+    if (input.value) {
+      headers[name] = input.value;
+    }
+  }
+  const apiKey = document.getElementById("api-key").value;
+  const token = document.getElementById("bearer-token").value;
+  if (apiKey) {
+    headers["X-API-Key"] = apiKey;
+  }
+  if (token) {
+    headers["Authorization"] = `Bearer ${token}`;
+  }
+  const init = { method: method.toUpperCase(), headers };
+  if (body) {
+    headers["Content-Type"] = "application/json";
+    init.body = body.value;
+  }
+  output.textContent = "…";
+  try {
+    // relative to the page, so the docs work behind a path prefix
+    const res = await fetch(new URL(uri.slice(1), document.baseURI), init);
+    const text = await res.text();
+    const lines = [`${res.status} ${res.statusText}`];
+    for (const [name, value] of res.headers) {
+      lines.push(`${name}: ${value}`);
+    }
+    output.textContent = `${lines.join("\n")}\n\n${text}`;
+  } catch (err) {
+    output.textContent = String(err);
+  }
+}
+
+function operation(spec, path, method, op) {
+  const inputs = { path: {}, header: {} };
+  const parameters = (op.parameters || []).map((param) => resolve(spec, param));
+  const rows = parameters.map((param) => {
+    const input = el("input", { type: "text" });
+    if (inputs[param.in]) {
+      inputs[param.in][param.name] = input;
+    }
+    return [
+      `${param.name}${param.required ? " *" : ""}`,
+      param.in,
+      param.description || "",
+      input,
+    ];
+  });
+
+  let body = null;
+  const content = op.requestBody && op.requestBody.content["application/json"];
+  if (content) {
+    body = el("textarea", { rows: 8, spellcheck: "false" });
+    body.value = JSON.stringify(example(spec, content.schema), null, 2);
+  }
+
+  const responses = Object.entries(op.responses).map(([status, res]) => {
+    res = resolve(spec, res);
+    const schemas = Object.entries(res.content || {}).map(([type, media]) =>
+      el("span", {}, `${type} `, typeOf(media.schema), " "),
+    );
+    return [status, res.description || "", el("span", {}, ...schemas)];
+  });
+
+  const output = el("pre", { class: "output" });
+  const button = el("button", { type: "button" }, "Send");
+  button.addEventListener("click", () => send(path, method, inputs, body, output));
+
+  return el(
+    "details",
+    { class: `operation ${method}`, id: `operation-${op.operationId}` },
+    el(
+      "summary",
+      {},
+      el("span", { class: "method" }, method.toUpperCase()),
+      el("code", {}, path),
+      el("span", { class: "summary" }, op.summary || ""),
+    ),
+    op.description ? el("p", { class: "text" }, op.description) : null,
+    rows.length ? table(["Parameter", "In", "Description", "Value"], rows) : null,
+    content ? el("p", {}, "Request body: ", typeOf(content.schema)) : null,
+    body,
+    el("div", {}, button),
+    output,
+    table(["Status", "Description", "Body"], responses),
+  );
+}
+
+function schema(spec, name, schema) {
+  const required = new Set(schema.required || []);
+  const rows = Object.entries(schema.properties || {}).map(([property, value]) => [
+    `${property}${required.has(property) ? " *" : ""}`,
+    typeOf(value),
+    value.description || resolve(spec, value).description || "",
+  ]);
+  return el(
+    "div",
+    { class: "schema", id: `schema-${name}` },
+    el("h3", {}, name),
+    schema.description ? el("p", { class: "text" }, schema.description) : null,
+    rows.length ? table(["Property", "Type", "Description"], rows) : el("p", {}, typeOf(schema)),
+  );
+}
+
+async function render() {
+  const operations = document.getElementById("operations");
+  let spec;
+  try {
+    spec = await (await fetch("openapi.json")).json();
+  } catch (err) {
+    operations.textContent = `failed to load openapi.json: ${err}`;
+    return;
+  }
+  document.getElementById("title").textContent = `${spec.info.title} ${spec.info.version}`;
+  document.getElementById("description").textContent = spec.info.description || "";
+  for (const [path, ops] of Object.entries(spec.paths)) {
+    for (const method of METHODS) {
+      if (ops[method]) {
+        operations.append(operation(spec, path, method, ops[method]));
+      }
+    }
+  }
+  const schemas = document.getElementById("schemas");
+  for (const [name, value] of Object.entries(spec.components.schemas)) {
+    schemas.append(schema(spec, name, value));
+  }
+}
+
+render();
@@ -0,0 +1,40 @@
+use {
+    axum::response::{Html, IntoResponse},
+    http::header,
+};
+
+/// The OpenAPI document describing this server, kept in sync with the router by tests
+pub const OPENAPI_YAML: &str = include_str!("../../../../../../openapi.yaml");
+/// [`OPENAPI_YAML`] converted to JSON by `scripts/openapi-json.sh`
+pub const OPENAPI_JSON: &str = include_str!("../../../../../../openapi.json");
+
+const DOCS_HTML: &str = include_str!("docs.html");
+const DOCS_JS: &str = include_str!("docs.js");
+const DOCS_CSS: &str = include_str!("docs.css");
+
+/// The docs page only loads what the server itself serves
+const DOCS_CSP: &str = "default-src 'self'; frame-ancestors 'none'";
+
+pub async fn openapi_json() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI_JSON)
+}
+
+pub async fn openapi_yaml() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/yaml")], OPENAPI_YAML)
+}
+
+/// Interactive docs for [`OPENAPI_JSON`]
+pub async fn docs() -> impl IntoResponse {
+    (
+        [(header::CONTENT_SECURITY_POLICY, DOCS_CSP)],
+        Html(DOCS_HTML),
+    )
+}
+
+pub async fn docs_js() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/javascript")], DOCS_JS)
+}
+
+pub async fn docs_css() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/css")], DOCS_CSS)
+}
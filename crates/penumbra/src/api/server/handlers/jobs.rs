@@ -0,0 +1,76 @@
+use {
+    crate::{
+        api::server::{
+            auth::Principal,
+            error::ApiError,
+            handlers::{normalize_hashes, request_fvk},
+            jobs::JobQueue,
+            request_log,
+            router::AppState,
+        },
+        error::Error,
+    },
+    axum::{
+        extract::{Path, State},
+        response::IntoResponse,
+        Extension, Json,
+    },
+    common::models::DisclosureRequestMultiple,
+    http::{header, StatusCode},
+    std::sync::Arc,
+};
+
+pub async fn create_disclosure_job(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<DisclosureRequestMultiple>,
+) -> Result<impl IntoResponse, ApiError> {
+    request_log::record_transactions(payload.transaction_hashes.len());
+    state.limits.check_batch(&payload.transaction_hashes)?;
+    let (transaction_hashes, invalid) = normalize_hashes(payload.transaction_hashes);
+    let fvk = request_fvk(
+        &state,
+        payload.full_viewing_key.as_deref(),
+        payload.wallet_id.as_deref(),
+    )
+    .await?;
+    if let Some(callback_url) = &payload.callback_url {
+        let Some(webhooks) = &state.webhooks else {
+            return Err(Error::InvalidRequest(
+                "callbacks are not enabled on this server".to_string(),
+            )
+            .into());
+        };
+        webhooks
+            .check_url(callback_url)
+            .await
+            .map_err(|err| Error::InvalidRequest(err.to_string()))?;
+    }
+    let owner = principal.map(|Extension(principal)| principal.as_str().to_string());
+    let job = JobQueue::submit(
+        &state,
+        owner,
+        fvk,
+        transaction_hashes,
+        invalid,
+        payload.callback_url,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/jobs/{}", job.job_id))],
+        Json(job),
+    ))
+}
+
+pub async fn get_disclosure_job(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(job_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let owner = principal.map(|Extension(principal)| principal.as_str().to_string());
+    let job = state.jobs.get(&job_id, owner.as_deref())?;
+
+    Ok(Json(job))
+}
@@ -0,0 +1,53 @@
+use {
+    crate::{
+        api::server::router::AppState,
+        metrics::{metrics as registry, render_gauge},
+    },
+    axum::{extract::State, response::IntoResponse},
+    http::header,
+    std::sync::Arc,
+};
+
+/// Prometheus scrape endpoint
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let mut out = registry().render();
+    render_gauge(
+        &mut out,
+        "disclosure_client_pool_size",
+        "Disclosure clients kept warm in the pool",
+        [(vec![], state.pool.len().await as f64)],
+    );
+    render_gauge(
+        &mut out,
+        "disclosure_cache_size",
+        "Disclosed transactions held in the cache",
+        [(vec![], state.cache.len() as f64)],
+    );
+    // aggregated rather than labeled by wallet, which would add a series per FVK
+    let lags = state.sync.sync_lags().await;
+    render_gauge(
+        &mut out,
+        "disclosure_registered_wallets",
+        "Wallets registered for background syncs",
+        [(vec![], lags.len() as f64)],
+    );
+    render_gauge(
+        &mut out,
+        "disclosure_wallet_sync_lag_min_blocks",
+        "Blocks the most synced registered wallet is behind the node",
+        lags.iter().min().map(|lag| (vec![], *lag as f64)),
+    );
+    render_gauge(
+        &mut out,
+        "disclosure_wallet_sync_lag_max_blocks",
+        "Blocks the least synced registered wallet is behind the node",
+        lags.iter().max().map(|lag| (vec![], *lag as f64)),
+    );
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        out,
+    )
+}
@@ -1,7 +1,236 @@
 pub mod disclose_transaction;
 pub mod disclose_transactions;
+pub mod disclose_transactions_stream;
+pub mod docs;
 pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod ready;
+pub mod wallets;
 
 pub use disclose_transaction::*;
 pub use disclose_transactions::*;
+pub use disclose_transactions_stream::*;
+pub use docs::*;
 pub use health::*;
+pub use jobs::*;
+pub use metrics::*;
+pub use ready::*;
+pub use wallets::*;
+
+use {
+    crate::{
+        api::server::{error::ApiError, request_log, router::AppState},
+        client::{fvk_hash, normalize_hash, DisclosureClient},
+        error::Error,
+    },
+    anyhow::anyhow,
+    axum::response::{IntoResponse, Response},
+    common::models::{DisclosureError, Transaction},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    penumbra_sdk_keys::FullViewingKey,
+    serde::Serialize,
+    sha2::{Digest, Sha256},
+    std::{
+        collections::{HashMap, HashSet},
+        future::Future,
+        str::FromStr,
+    },
+};
+
+/// Response header reporting the height the wallet's view was synced to
+/// when the disclosure was generated
+pub const SYNCED_HEIGHT_HEADER: &str = "x-synced-height";
+
+/// Request header carrying the wallet handle of `GET /disclose/transaction/{transactionHash}`,
+/// so handles stay out of URLs
+pub const WALLET_ID_HEADER: &str = "x-wallet-id";
+
+/// Resolves the FVK a disclosure request is for, given either directly or as a wallet handle
+async fn request_fvk(
+    state: &AppState,
+    full_viewing_key: Option<&str>,
+    wallet_id: Option<&str>,
+) -> Result<FullViewingKey, Error> {
+    let fvk = match (full_viewing_key, wallet_id) {
+        (Some(fvk), None) => {
+            FullViewingKey::from_str(fvk).map_err(|err| Error::InvalidFvk(anyhow!(err)))?
+        }
+        (None, Some(wallet_id)) => state.wallets.resolve(wallet_id).await?,
+        _ => {
+            return Err(Error::InvalidRequest(
+                "exactly one of fullViewingKey and walletId is required".to_string(),
+            ))
+        }
+    };
+    request_log::record_fvk(&fvk);
+    Ok(fvk)
+}
+
+/// Discloses a batch of normalized, distinct hashes, serving the ones already
+/// disclosed from the cache and caching the others
+///
+/// The wallet is only synced, with `client`, if some disclosures aren't cached.
+/// Returns one result per hash, along with the height the wallet was synced to,
+/// or the latest height of the cached disclosures if it wasn't synced.
+pub(crate) async fn disclose_cached<Fut, E>(
+    state: &AppState,
+    fvk: &FullViewingKey,
+    transaction_hashes: &[String],
+    client: impl FnOnce() -> Fut,
+) -> Result<(Vec<Result<Transaction, Error>>, Option<u64>), E>
+where
+    Fut: Future<Output = Result<(DisclosureClient, u64), E>>,
+{
+    let wallet = fvk_hash(fvk);
+    let mut cached = HashMap::new();
+    let mut missing = Vec::new();
+    for hash in transaction_hashes {
+        match state.cache.get(&wallet, hash) {
+            Some(disclosure) => {
+                cached.insert(hash.as_str(), disclosure);
+            }
+            None => missing.push(hash.clone()),
+        }
+    }
+    let mut synced_height = cached
+        .values()
+        .map(|disclosure| disclosure.synced_height)
+        .max();
+
+    let mut disclosed = HashMap::new();
+    if !missing.is_empty() {
+        let (dc, height) = client().await?;
+        synced_height = Some(height);
+        let results = dc.transactions(&missing, state.lookup_concurrency).await;
+        for (hash, result) in missing.iter().zip(results) {
+            if let Ok(transaction) = &result {
+                state.cache.insert(&wallet, hash, transaction, height);
+            }
+            disclosed.insert(hash.as_str(), result);
+        }
+    }
+
+    let results = transaction_hashes
+        .iter()
+        .map(|hash| match cached.remove(hash.as_str()) {
+            Some(disclosure) => Ok(disclosure.transaction),
+            None => disclosed
+                .remove(hash.as_str())
+                .expect("every hash that isn't cached is looked up"),
+        })
+        .collect();
+    Ok((results, synced_height))
+}
+
+/// Responds with `body` as JSON tagged with its ETag
+///
+/// If the request's `If-None-Match` already has the ETag, a GET is answered
+/// with `304 Not Modified` and any other method fails with
+/// [`ApiError::PreconditionFailed`], as RFC 9110 only allows a 304 for GET and HEAD.
+fn json_with_etag(
+    method: &Method,
+    headers: &HeaderMap,
+    synced_height: Option<u64>,
+    body: &impl Serialize,
+) -> Result<Response, ApiError> {
+    let body = serde_json::to_vec(body).map_err(|err| Error::Internal(err.into()))?;
+    let etag = format!("\"{}\"", hex::encode(&Sha256::digest(&body)[..16]));
+    let mut res = if !etag_matches(headers.get(header::IF_NONE_MATCH), &etag) {
+        ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+    } else if method == Method::GET || method == Method::HEAD {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ApiError::PreconditionFailed.into_response()
+    };
+    let headers = res.headers_mut();
+    headers.insert(
+        header::ETAG,
+        HeaderValue::from_str(&etag).expect("etags are valid header values"),
+    );
+    if let Some(synced_height) = synced_height {
+        headers.insert(SYNCED_HEIGHT_HEADER, synced_height.into());
+    }
+    Ok(res)
+}
+
+/// Whether `If-None-Match` lists `etag`, using the weak comparison conditional GETs call for
+fn etag_matches(if_none_match: Option<&HeaderValue>, etag: &str) -> bool {
+    let Some(if_none_match) = if_none_match.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Normalizes the transaction hashes of a batch disclosure, dropping duplicates
+///
+/// Malformed hashes are returned as `invalid_hash` errors right away, so they
+/// fail without waiting for the wallet to sync.
+fn normalize_hashes(transaction_hashes: Vec<String>) -> (Vec<String>, Vec<DisclosureError>) {
+    let mut seen = HashSet::with_capacity(transaction_hashes.len());
+    let mut valid = Vec::with_capacity(transaction_hashes.len());
+    let mut invalid = Vec::new();
+    for hash in transaction_hashes {
+        match normalize_hash(&hash) {
+            Ok(normalized) => {
+                if seen.insert(normalized.clone()) {
+                    valid.push(normalized);
+                }
+            }
+            Err(err) => {
+                if seen.insert(hash.clone()) {
+                    invalid.push(err.disclosure_error(hash));
+                }
+            }
+        }
+    }
+    (valid, invalid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = r#""abc""#;
+        assert!(!etag_matches(None, etag));
+        for if_none_match in [r#""abc""#, r#"W/"abc""#, r#""xyz", "abc""#, "*"] {
+            assert!(
+                etag_matches(Some(&HeaderValue::from_static(if_none_match)), etag),
+                "{if_none_match}"
+            );
+        }
+        for if_none_match in [r#""xyz""#, "abc", ""] {
+            assert!(
+                !etag_matches(Some(&HeaderValue::from_static(if_none_match)), etag),
+                "{if_none_match}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_hashes() {
+        const TX_HASH: &str = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+
+        let (valid, invalid) = normalize_hashes(vec![
+            format!("0x{}", TX_HASH.to_uppercase()),
+            "0xabc123".to_string(),
+            TX_HASH.to_string(),
+            "0xabc123".to_string(),
+            "not a hash".to_string(),
+        ]);
+        assert_eq!(valid, [TX_HASH]);
+        let invalid = invalid
+            .iter()
+            .map(|err| (err.transaction_hash.as_str(), err.error.code.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            invalid,
+            [("0xabc123", "invalid_hash"), ("not a hash", "invalid_hash")]
+        );
+    }
+}
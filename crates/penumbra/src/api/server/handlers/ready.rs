@@ -0,0 +1,110 @@
+use {
+    crate::{api::server::router::AppState, metrics::metrics},
+    anyhow::{Context, Result},
+    axum::{extract::State, response::IntoResponse, Json},
+    chrono::Utc,
+    http::StatusCode,
+    penumbra_sdk_proto::{
+        core::component::compact_block::v1::{
+            query_service_client::QueryServiceClient as CompactBlockQueryServiceClient,
+            CompactBlockRequest,
+        },
+        util::tendermint_proxy::v1::{
+            tendermint_proxy_service_client::TendermintProxyServiceClient, GetStatusRequest,
+        },
+    },
+    serde_json::json,
+    std::{sync::Arc, time::Duration},
+    tonic::transport::Endpoint,
+};
+
+/// How long the node has to answer a readiness probe
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Readiness probe, which unlike [`super::health`] checks the node can serve disclosures
+///
+/// Responds with 503 if the node's Tendermint proxy or compact block service
+/// don't respond, or if the node is on a different chain than expected.
+pub async fn ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let status = tokio::time::timeout(READY_TIMEOUT, node_status(&state.url))
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("node timed out")));
+    let (chain_id, latest_height) = match status {
+        Ok(status) => status,
+        Err(err) => {
+            log::warn!("readiness probe failed: {err:#}");
+            // only the outermost context, the rest may describe the node's internals
+            return unavailable(err.to_string(), None, None);
+        }
+    };
+    if let Some(expected) = &state.expected_chain_id {
+        if *expected != chain_id {
+            log::warn!("node is on chain {chain_id}, expected {expected}");
+            return unavailable(
+                format!("node is on chain {chain_id}, expected {expected}"),
+                Some(chain_id),
+                Some(latest_height),
+            );
+        }
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": "ready",
+            "timestamp": Utc::now(),
+            "chainId": chain_id,
+            "latestHeight": latest_height,
+        })),
+    )
+}
+
+fn unavailable(
+    reason: String,
+    chain_id: Option<String>,
+    latest_height: Option<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let mut body = json!({
+        "status": "unavailable",
+        "timestamp": Utc::now(),
+        "reason": reason,
+    });
+    // only known once the node answered
+    if let Some(chain_id) = chain_id {
+        body["chainId"] = chain_id.into();
+    }
+    if let Some(latest_height) = latest_height {
+        body["latestHeight"] = latest_height.into();
+    }
+    (StatusCode::SERVICE_UNAVAILABLE, Json(body))
+}
+
+/// Returns the chain id and latest height of the node, checking it can serve compact blocks
+async fn node_status(url: &str) -> Result<(String, u64)> {
+    let channel = Endpoint::from_shared(url.to_string())
+        .with_context(|| "invalid grpc url")?
+        .connect()
+        .await
+        .with_context(|| "failed to connect to node")?;
+    let status = TendermintProxyServiceClient::new(channel.clone())
+        .get_status(GetStatusRequest {})
+        .await
+        .inspect_err(|_| metrics().record_upstream_error("get_status"))
+        .with_context(|| "tendermint proxy is unavailable")?
+        .into_inner();
+    let chain_id = status
+        .node_info
+        .with_context(|| "node info is None")?
+        .network;
+    let latest_height = status
+        .sync_info
+        .with_context(|| "sync info is None")?
+        .latest_block_height;
+    CompactBlockQueryServiceClient::new(channel)
+        .compact_block(CompactBlockRequest {
+            height: latest_height,
+        })
+        .await
+        .inspect_err(|_| metrics().record_upstream_error("compact_block"))
+        .with_context(|| "compact block service is unavailable")?;
+    Ok((chain_id, latest_height))
+}
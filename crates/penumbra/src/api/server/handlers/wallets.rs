@@ -0,0 +1,41 @@
+use {
+    crate::{
+        api::server::{error::ApiError, request_log, router::AppState},
+        client::fvk_hash,
+        error::Error,
+    },
+    anyhow::anyhow,
+    axum::{
+        extract::{Path, State},
+        response::IntoResponse,
+        Json,
+    },
+    common::models::{Wallet, WalletRegistrationRequest},
+    http::StatusCode,
+    penumbra_sdk_keys::FullViewingKey,
+    std::{str::FromStr, sync::Arc},
+};
+
+pub async fn register_wallet(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<WalletRegistrationRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let fvk = FullViewingKey::from_str(&payload.full_viewing_key)
+        .map_err(|err| Error::InvalidFvk(anyhow!(err)))?;
+    request_log::record_fvk(&fvk);
+    let wallet_id = state.wallets.register(&fvk).await?;
+
+    Ok((StatusCode::CREATED, Json(Wallet { wallet_id })))
+}
+
+/// Revokes a wallet handle, and stops syncing and serving cached disclosures for its FVK
+pub async fn revoke_wallet(
+    State(state): State<Arc<AppState>>,
+    Path(wallet_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let fvk = state.wallets.revoke(&wallet_id).await?;
+    state.sync.unregister(&fvk).await;
+    state.cache.remove_wallet(&fvk_hash(&fvk));
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,587 @@
+use {
+    crate::{
+        api::server::{
+            error::ApiError,
+            handlers::{disclose_cached, disclosure_result},
+            router::AppState,
+            sync::SyncProgress,
+            wallets::Encrypted,
+        },
+        error::{Error, Result},
+    },
+    anyhow::Context,
+    camino::{Utf8Path, Utf8PathBuf},
+    chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit},
+    chrono::Utc,
+    common::models::{
+        callback_delivery, disclosure_job::Status, CallbackAttempt, CallbackDelivery,
+        DisclosedTransactionResult, DisclosureError, DisclosureJob, DisclosureJobProgress,
+    },
+    penumbra_sdk_keys::FullViewingKey,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tokio::sync::Semaphore,
+    tokio_util::sync::CancellationToken,
+    tracing::{Instrument, Span},
+};
+
+/// Disclosure jobs that run in the background on a bounded pool of workers
+///
+/// Jobs are persisted to `dir`, one file per job encrypted under the wallet
+/// key, so completed results survive a restart. The FVK a job was submitted
+/// with is never persisted, so jobs that were still running when the server
+/// stopped are marked as failed, as are callback deliveries that were still
+/// pending.
+///
+/// At most `max_queued` jobs may be queued or running at once, further
+/// submissions are rejected with [`ApiError::JobQueueFull`].
+pub struct JobQueue {
+    dir: Option<Utf8PathBuf>,
+    /// encrypts persisted jobs, set whenever `dir` is
+    cipher: Option<ChaCha20Poly1305>,
+    workers: Semaphore,
+    max_queued: usize,
+    retention: Duration,
+    jobs: RwLock<HashMap<String, StoredJob>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredJob {
+    /// principal that submitted the job, only they may poll it
+    owner: Option<String>,
+    job: DisclosureJob,
+}
+
+impl JobQueue {
+    /// Loads the jobs persisted in `dir` and encrypted under `key`, keeping
+    /// jobs in memory only if `dir` is unset
+    pub fn load(
+        dir: Option<Utf8PathBuf>,
+        key: Option<&Key>,
+        workers: usize,
+        max_queued: usize,
+        retention: Duration,
+    ) -> anyhow::Result<Self> {
+        let cipher = match (&dir, key) {
+            (Some(_), None) => anyhow::bail!("persisting disclosure jobs requires the wallet key"),
+            (Some(_), Some(key)) => Some(ChaCha20Poly1305::new(key)),
+            (None, _) => None,
+        };
+        let mut queue = Self {
+            dir,
+            cipher,
+            workers: Semaphore::new(workers.max(1)),
+            max_queued: max_queued.max(1),
+            retention,
+            jobs: Default::default(),
+        };
+        if let Some(dir) = &queue.dir {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create {dir}"))?;
+            for entry in dir
+                .read_dir_utf8()
+                .with_context(|| format!("failed to read {dir}"))?
+            {
+                let path = entry?.into_path();
+                if path.extension() != Some("json") {
+                    continue;
+                }
+                // a file that can't be read is skipped rather than keeping the server down
+                let mut stored = match queue.read(&path) {
+                    Ok(stored) => stored,
+                    Err(err) => {
+                        log::warn!("skipping disclosure job {path}: {err:#}");
+                        continue;
+                    }
+                };
+                let mut changed = false;
+                let interrupted_callback = stored
+                    .job
+                    .callback
+                    .as_mut()
+                    .filter(|callback| callback.status == callback_delivery::Status::Pending);
+                if let Some(callback) = interrupted_callback {
+                    callback.status = callback_delivery::Status::Failed;
+                    changed = true;
+                }
+                if !is_finished(stored.job.status) {
+                    fail(&mut stored.job, &Error::Interrupted);
+                    changed = true;
+                }
+                if changed {
+                    std::fs::write(&path, queue.encode(&stored)?)
+                        .with_context(|| format!("failed to write {path}"))?;
+                }
+                queue
+                    .jobs
+                    .get_mut()
+                    .unwrap()
+                    .insert(stored.job.job_id.clone(), stored);
+            }
+        }
+        Ok(queue)
+    }
+
+    /// Reads and decrypts a persisted job
+    fn read(&self, path: &Utf8Path) -> anyhow::Result<StoredJob> {
+        let encrypted: Encrypted = serde_json::from_slice(&std::fs::read(path)?)?;
+        let job_id = path.file_stem().unwrap_or_default();
+        let cipher = self.cipher.as_ref().expect("persisted jobs are encrypted");
+        Ok(serde_json::from_slice(
+            &encrypted.open(cipher, job_id.as_bytes())?,
+        )?)
+    }
+
+    /// Serializes a job to persist, encrypted and bound to its id
+    fn encode(&self, stored: &StoredJob) -> anyhow::Result<Vec<u8>> {
+        let cipher = self.cipher.as_ref().expect("persisted jobs are encrypted");
+        let encrypted = Encrypted::seal(
+            cipher,
+            &serde_json::to_vec(stored)?,
+            stored.job.job_id.as_bytes(),
+        )?;
+        Ok(serde_json::to_vec(&encrypted)?)
+    }
+
+    /// Returns the job with `job_id`, if it was submitted by `owner`
+    pub fn get(&self, job_id: &str, owner: Option<&str>) -> Result<DisclosureJob> {
+        let jobs = self.jobs.read().unwrap();
+        match jobs.get(job_id) {
+            Some(stored) if stored.owner.as_deref() == owner => Ok(stored.job.clone()),
+            // other callers' jobs are indistinguishable from missing ones
+            _ => Err(Error::JobNotFound),
+        }
+    }
+
+    /// Queues a disclosure of `transaction_hashes` for `fvk`, returning the new job
+    ///
+    /// Fails with [`ApiError::JobQueueFull`] if `max_queued` jobs are already queued or running.
+    /// The `invalid` hashes that were already rejected are added to the job's result.
+    /// If a `callback_url` is given, the outcome is delivered to it once the job is done.
+    pub async fn submit(
+        state: &Arc<AppState>,
+        owner: Option<String>,
+        fvk: FullViewingKey,
+        transaction_hashes: Vec<String>,
+        invalid: Vec<DisclosureError>,
+        callback_url: Option<String>,
+    ) -> Result<DisclosureJob, ApiError> {
+        let now = Utc::now().timestamp();
+        let mut job =
+            DisclosureJob::new(uuid::Uuid::new_v4().to_string(), Status::Queued, now, now);
+        job.callback = callback_url.map(|url| CallbackDelivery {
+            url,
+            status: callback_delivery::Status::Pending,
+            attempts: vec![],
+        });
+        let stored = StoredJob { owner, job };
+        let job = stored.job.clone();
+        {
+            let mut jobs = state.jobs.jobs.write().unwrap();
+            let unfinished = jobs
+                .values()
+                .filter(|stored| !is_finished(stored.job.status))
+                .count();
+            if unfinished >= state.jobs.max_queued {
+                return Err(ApiError::JobQueueFull(state.jobs.max_queued));
+            }
+            jobs.insert(job.job_id.clone(), stored.clone());
+        }
+        state.jobs.persist(&stored).await.map_err(Error::from)?;
+
+        // jobs outlive the request that submitted them, so they are traced on their own
+        let span = tracing::info_span!(parent: None, "disclosure_job", job_id = %job.job_id);
+        span.follows_from(Span::current());
+        state.shutdown.spawn(
+            run(
+                state.clone(),
+                job.job_id.clone(),
+                fvk,
+                transaction_hashes,
+                invalid,
+            )
+            .instrument(span),
+        );
+        Ok(job)
+    }
+
+    /// Applies `update` to the job, persisting it if `persist` is set
+    async fn update(&self, job_id: &str, persist: bool, update: impl FnOnce(&mut DisclosureJob)) {
+        let stored = {
+            let mut jobs = self.jobs.write().unwrap();
+            let Some(stored) = jobs.get_mut(job_id) else {
+                return;
+            };
+            update(&mut stored.job);
+            stored.clone()
+        };
+        if persist {
+            if let Err(err) = self.persist(&stored).await {
+                log::warn!("failed to persist job {job_id}: {err:#}");
+            }
+        }
+    }
+
+    fn record_callback_attempt(&self, job_id: &str, attempt: CallbackAttempt) {
+        if let Some(callback) = self
+            .jobs
+            .write()
+            .unwrap()
+            .get_mut(job_id)
+            .and_then(|stored| stored.job.callback.as_mut())
+        {
+            callback.attempts.push(attempt);
+        }
+    }
+
+    /// Records the progress of the job's sync, which is `queued` while it waits for a sync slot
+    fn set_progress(&self, job_id: &str, progress: SyncProgress) {
+        let mut jobs = self.jobs.write().unwrap();
+        let Some(stored) = jobs.get_mut(job_id) else {
+            return;
+        };
+        match progress {
+            SyncProgress::Queued => set_status(&mut stored.job, Status::Queued),
+            SyncProgress::Syncing {
+                synced_height,
+                latest_height,
+            } => {
+                if stored.job.status == Status::Queued {
+                    set_status(&mut stored.job, Status::Syncing);
+                }
+                stored.job.progress = Some(DisclosureJobProgress {
+                    synced_height,
+                    latest_height,
+                });
+            }
+        }
+    }
+
+    async fn persist(&self, stored: &StoredJob) -> anyhow::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let path = dir.join(format!("{}.json", stored.job.job_id));
+        // write to a temporary file first so a crash never leaves a truncated job behind
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, self.encode(stored)?)
+            .await
+            .with_context(|| format!("failed to write {tmp_path}"))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("failed to persist {path}"))
+    }
+
+    /// Drops finished jobs that haven't been updated within the retention period
+    pub async fn remove_expired(&self) {
+        let cutoff = Utc::now().timestamp() - self.retention.as_secs() as i64;
+        let expired = {
+            let mut jobs = self.jobs.write().unwrap();
+            let expired = jobs
+                .values()
+                .filter(|stored| is_finished(stored.job.status) && stored.job.updated_at < cutoff)
+                .map(|stored| stored.job.job_id.clone())
+                .collect::<Vec<_>>();
+            for job_id in &expired {
+                jobs.remove(job_id);
+            }
+            expired
+        };
+        if let Some(dir) = &self.dir {
+            for job_id in &expired {
+                let path = dir.join(format!("{job_id}.json"));
+                if let Err(err) = tokio::fs::remove_file(&path).await {
+                    log::warn!("failed to remove {path}: {err}");
+                }
+            }
+        }
+        if !expired.is_empty() {
+            log::info!("removed {} expired disclosure jobs", expired.len());
+        }
+    }
+
+    /// Periodically removes expired jobs until the queue is dropped
+    pub fn spawn_reaper(self: &Arc<Self>) {
+        let queue = Arc::downgrade(self);
+        let period = self
+            .retention
+            .clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(queue) = queue.upgrade() else {
+                    return;
+                };
+                queue.remove_expired().await;
+            }
+        });
+    }
+}
+
+/// Runs a job once a worker is free, recording its progress and outcome
+///
+/// Jobs still running when the server shuts down fail as interrupted.
+async fn run(
+    state: Arc<AppState>,
+    job_id: String,
+    fvk: FullViewingKey,
+    transaction_hashes: Vec<String>,
+    invalid: Vec<DisclosureError>,
+) {
+    let jobs = &state.jobs;
+    let cancel = state.shutdown.token();
+    let permit = tokio::select! {
+        permit = jobs.workers.acquire() => permit.expect("job semaphore is never closed"),
+        _ = cancel.cancelled() => {
+            jobs.update(&job_id, true, |job| fail(job, &Error::Interrupted))
+                .await;
+            return;
+        }
+    };
+    jobs.update(&job_id, true, |job| set_status(job, Status::Syncing))
+        .await;
+
+    let result: Result<DisclosedTransactionResult> = async {
+        let (results, _) = disclose_cached(&state, &fvk, &transaction_hashes, || async {
+            let synced = state
+                .sync
+                .client_with_progress(&fvk, cancel.clone(), |progress| {
+                    jobs.set_progress(&job_id, progress)
+                })
+                .await?;
+            jobs.update(&job_id, true, |job| set_status(job, Status::Disclosing))
+                .await;
+            Ok::<_, Error>(synced)
+        })
+        .await?;
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        Ok(disclosure_result(transaction_hashes, results, invalid))
+    }
+    .await;
+
+    match result {
+        Ok(result) => {
+            jobs.update(&job_id, true, |job| {
+                set_status(job, Status::Completed);
+                job.result = Some(result);
+            })
+            .await
+        }
+        Err(Error::Cancelled) => {
+            log::warn!("disclosure job {job_id} was interrupted by the shutdown");
+            jobs.update(&job_id, true, |job| fail(job, &Error::Interrupted))
+                .await
+        }
+        Err(err) => {
+            log::warn!("disclosure job {job_id} failed: {}", err.details());
+            jobs.update(&job_id, true, |job| fail(job, &err)).await
+        }
+    }
+
+    // retrying a callback shouldn't hold up other jobs
+    drop(permit);
+    deliver_callback(&state, &job_id, &cancel).await;
+}
+
+/// Delivers the outcome of a finished job to its callback URL, if it has one
+///
+/// Deliveries cut short by the shutdown are left pending, and marked as failed on restart.
+async fn deliver_callback(state: &AppState, job_id: &str, cancel: &CancellationToken) {
+    let Some(webhooks) = &state.webhooks else {
+        return;
+    };
+    let Some(job) = state
+        .jobs
+        .jobs
+        .read()
+        .unwrap()
+        .get(job_id)
+        .map(|stored| stored.job.clone())
+    else {
+        return;
+    };
+    let Some(callback) = &job.callback else {
+        return;
+    };
+    let (job_status, body) = match job.status {
+        Status::Completed => ("completed", serde_json::to_vec(&job.result)),
+        _ => ("failed", serde_json::to_vec(&job.error)),
+    };
+    let body = body.expect("job outcomes always serialize");
+
+    let delivered = tokio::select! {
+        delivered = webhooks.deliver(&callback.url, job_id, job_status, body, |attempt| {
+            state.jobs.record_callback_attempt(job_id, attempt)
+        }) => delivered,
+        _ = cancel.cancelled() => return,
+    };
+    let status = if delivered {
+        callback_delivery::Status::Delivered
+    } else {
+        callback_delivery::Status::Failed
+    };
+    state
+        .jobs
+        .update(job_id, true, |job| {
+            if let Some(callback) = &mut job.callback {
+                callback.status = status;
+            }
+        })
+        .await;
+}
+
+fn is_finished(status: Status) -> bool {
+    matches!(status, Status::Completed | Status::Failed)
+}
+
+fn set_status(job: &mut DisclosureJob, status: Status) {
+    job.status = status;
+    job.updated_at = Utc::now().timestamp();
+}
+
+fn fail(job: &mut DisclosureJob, err: &Error) {
+    set_status(job, Status::Failed);
+    job.error = Some(err.into());
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::api::server::{test_util::FVK, ServerOptions},
+        std::str::FromStr,
+    };
+
+    #[tokio::test]
+    async fn test_job_queue_persists_finished_jobs() {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("jobs-{}", std::process::id()));
+        let key = Key::from([7u8; 32]);
+        let queue = JobQueue::load(
+            Some(dir.clone()),
+            Some(&key),
+            1,
+            10,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+        let now = Utc::now().timestamp();
+        for (job_id, status) in [
+            ("completed", Status::Completed),
+            ("running", Status::Syncing),
+        ] {
+            let stored = StoredJob {
+                owner: Some("ops".to_string()),
+                job: DisclosureJob::new(job_id.to_string(), status, now, now),
+            };
+            queue.persist(&stored).await.unwrap();
+        }
+        // jobs are encrypted at rest, and are skipped when loaded under another key
+        let contents = std::fs::read_to_string(dir.join("completed.json")).unwrap();
+        assert!(!contents.contains("ops"));
+        let queue = JobQueue::load(
+            Some(dir.clone()),
+            Some(&Key::from([8u8; 32])),
+            1,
+            10,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        assert!(matches!(
+            queue.get("completed", Some("ops")),
+            Err(Error::JobNotFound)
+        ));
+        // as are unencrypted jobs, which could claim any owner
+        let planted = StoredJob {
+            owner: Some("ops".to_string()),
+            job: DisclosureJob::new("planted".to_string(), Status::Completed, now, now),
+        };
+        std::fs::write(
+            dir.join("planted.json"),
+            serde_json::to_vec(&planted).unwrap(),
+        )
+        .unwrap();
+
+        // jobs that were still running when the server stopped are failed on restart
+        let queue = JobQueue::load(
+            Some(dir.clone()),
+            Some(&key),
+            1,
+            10,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let job = queue.get("completed", Some("ops")).unwrap();
+        assert_eq!(job.status, Status::Completed);
+        assert!(matches!(
+            queue.get("planted", Some("ops")),
+            Err(Error::JobNotFound)
+        ));
+        let job = queue.get("running", Some("ops")).unwrap();
+        assert_eq!(job.status, Status::Failed);
+        assert_eq!(job.error.unwrap().code, "interrupted");
+
+        // only the submitter can see the job
+        assert!(matches!(
+            queue.get("completed", None),
+            Err(Error::JobNotFound)
+        ));
+
+        // finished jobs are removed once they're past the retention period
+        let queue = JobQueue::load(Some(dir.clone()), Some(&key), 1, 10, Duration::ZERO).unwrap();
+        queue
+            .update("completed", false, |job| job.updated_at -= 10)
+            .await;
+        queue.remove_expired().await;
+        assert!(matches!(
+            queue.get("completed", Some("ops")),
+            Err(Error::JobNotFound)
+        ));
+        assert!(!dir.join("completed.json").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_job_queue_full() {
+        let state = Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    job_workers: 1,
+                    max_queued_jobs: 1,
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        );
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+        let submit = || {
+            JobQueue::submit(
+                &state,
+                None,
+                fvk.clone(),
+                vec!["ab".repeat(32)],
+                vec![],
+                None,
+            )
+        };
+
+        // the only worker is busy, so the first job stays queued and fills the queue
+        let permit = state.jobs.workers.acquire().await.unwrap();
+        submit().await.unwrap();
+        let err = submit().await.unwrap_err();
+        assert!(matches!(err, ApiError::JobQueueFull(1)));
+        assert_eq!(err.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(err.retry_after().is_some());
+        drop(permit);
+    }
+}
@@ -0,0 +1,143 @@
+use {
+    crate::api::server::error::ApiError,
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    http::{header, StatusCode},
+    std::time::Duration,
+};
+
+/// Bounds on what a single request may ask of the server
+#[derive(Clone, Debug)]
+pub struct RequestLimits {
+    /// largest accepted request body, in bytes
+    pub max_body_size: usize,
+    /// how long a request may take until its response starts
+    pub request_timeout: Duration,
+    /// most transaction hashes accepted in one batch disclosure
+    pub max_transaction_hashes: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_size: 1024 * 1024,
+            request_timeout: Duration::from_secs(120),
+            max_transaction_hashes: 1000,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Rejects batches with more transaction hashes than allowed
+    pub fn check_batch(&self, transaction_hashes: &[String]) -> Result<(), ApiError> {
+        if transaction_hashes.len() > self.max_transaction_hashes {
+            return Err(ApiError::TooManyTransactions(self.max_transaction_hashes));
+        }
+        Ok(())
+    }
+}
+
+/// Middleware enforcing the body size and timeout limits with standard error responses
+///
+/// Bodies without a content length are cut off by [`axum::extract::DefaultBodyLimit`],
+/// whose plain text rejection is replaced here.
+pub async fn enforce_limits(
+    State(limits): State<RequestLimits>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > limits.max_body_size) {
+        return ApiError::PayloadTooLarge(limits.max_body_size).into_response();
+    }
+    match tokio::time::timeout(limits.request_timeout, next.run(req)).await {
+        Ok(res) if res.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            ApiError::PayloadTooLarge(limits.max_body_size).into_response()
+        }
+        Ok(res) => res,
+        Err(_) => ApiError::RequestTimeout.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        axum::{body::Body, extract::DefaultBodyLimit, middleware, routing::post, Router},
+        common::models::error::Error as CommonError,
+        http_body_util::BodyExt,
+        tower::ServiceExt,
+    };
+
+    #[tokio::test]
+    async fn test_request_limits() {
+        let limits = RequestLimits {
+            max_body_size: 16,
+            request_timeout: Duration::from_millis(50),
+            max_transaction_hashes: 1,
+        };
+        let router = Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .route(
+                "/slow",
+                post(|| tokio::time::sleep(Duration::from_secs(10))),
+            )
+            .layer(DefaultBodyLimit::max(limits.max_body_size))
+            .layer(middleware::from_fn_with_state(
+                limits.clone(),
+                enforce_limits,
+            ));
+
+        let chunked = || {
+            Body::from_stream(futures::stream::iter(
+                ["0123456789", "0123456789"].map(Ok::<_, std::io::Error>),
+            ))
+        };
+        let requests = [
+            (
+                Request::post("/echo").body(Body::from("small")).unwrap(),
+                StatusCode::OK,
+                None,
+            ),
+            (
+                Request::post("/echo")
+                    .body(Body::from("x".repeat(17)))
+                    .unwrap(),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Some("payload_too_large"),
+            ),
+            // without a content length the body is only cut off while it is read
+            (
+                Request::post("/echo").body(chunked()).unwrap(),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Some("payload_too_large"),
+            ),
+            (
+                Request::post("/slow").body(Body::empty()).unwrap(),
+                StatusCode::REQUEST_TIMEOUT,
+                Some("request_timeout"),
+            ),
+        ];
+        for (request, status, code) in requests {
+            let res = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(res.status(), status);
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            if let Some(code) = code {
+                let err: CommonError = serde_json::from_slice(&body).unwrap();
+                assert_eq!(err.code, code);
+            }
+        }
+
+        assert!(limits.check_batch(&["a".to_string()]).is_ok());
+        assert!(matches!(
+            limits.check_batch(&["a".to_string(), "b".to_string()]),
+            Err(ApiError::TooManyTransactions(1))
+        ));
+    }
+}
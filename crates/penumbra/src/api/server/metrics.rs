@@ -0,0 +1,30 @@
+use {
+    crate::metrics::metrics,
+    axum::{extract::MatchedPath, extract::Request, middleware::Next, response::Response},
+    std::time::Instant,
+};
+
+/// Middleware recording the count and latency of requests by route
+///
+/// Requests are labeled with the route template rather than the requested
+/// path, so ids in the path don't create a time series per request.
+pub async fn track_requests(
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = req.method().clone();
+    let route = matched_path
+        .as_ref()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let res = next.run(req).await;
+    metrics().observe_request(
+        method.as_str(),
+        &route,
+        res.status().as_u16(),
+        started.elapsed(),
+    );
+    res
+}
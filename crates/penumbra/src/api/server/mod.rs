@@ -1,50 +1,162 @@
+pub mod auth;
+pub mod cache;
+pub mod config;
+pub mod error;
 pub mod handlers;
+pub mod jobs;
+pub mod limits;
+pub mod metrics;
+pub mod pool;
+pub mod rate_limit;
+pub mod request_log;
 pub mod router;
+pub mod shutdown;
+pub mod sync;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod tls;
+pub mod wallets;
+pub mod webhooks;
 
 use {
     anyhow::{Context, Result},
-    tokio::signal,
+    auth::AuthConfig,
+    camino::Utf8PathBuf,
+    limits::RequestLimits,
+    rate_limit::RateLimit,
+    router::AppState,
+    std::{sync::Arc, time::Duration},
+    tls::{TlsConfig, TlsListener},
 };
 
-pub async fn start_api(url: String, listen_url: String) -> Result<()> {
-    log::info!("Starting API server - connecting to Penumbra gRPC at {url}");
-    log::info!("API server listening on {listen_url}");
-    let router = router::new(url);
-    Ok(axum::serve(
-        tokio::net::TcpListener::bind(listen_url)
-            .await
-            .with_context(|| "failed to create listener")?,
-        router,
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await?)
+/// Tunables for the disclosure clients managed by the api server
+#[derive(Clone, Debug)]
+pub struct ServerOptions {
+    /// how long a pooled client may go unused before it is dropped
+    pub client_idle_timeout: Duration,
+    /// maximum number of wallets synced concurrently in the background
+    pub sync_concurrency: usize,
+    /// maximum number of syncs running at once, inline and in the background
+    pub max_concurrent_syncs: usize,
+    /// how often registered wallets are synced in the background
+    pub sync_interval: Duration,
+    /// maximum number of concurrent lookups while disclosing a batch of transactions
+    pub lookup_concurrency: usize,
+    /// maximum number of disclosed transactions cached, caching is disabled if 0
+    pub disclosure_cache_size: usize,
+    /// credentials required by the disclosure endpoints, if unset the api is unauthenticated
+    pub auth: Option<AuthConfig>,
+    /// default rate limit of every caller of the disclosure endpoints, unlimited if unset
+    pub rate_limit: Option<RateLimit>,
+    /// file registered wallets are persisted to, if unset they are only kept in memory
+    pub wallet_store: Option<Utf8PathBuf>,
+    /// file with the hex encoded key the wallet store is encrypted under
+    pub wallet_key_file: Option<Utf8PathBuf>,
+    /// maximum number of disclosure jobs run concurrently
+    pub job_workers: usize,
+    /// maximum number of disclosure jobs queued or running, further jobs are rejected
+    pub max_queued_jobs: usize,
+    /// directory disclosure jobs are persisted to encrypted under the wallet key,
+    /// if unset they are only kept in memory
+    pub job_dir: Option<Utf8PathBuf>,
+    /// how long finished disclosure jobs are kept around
+    pub job_retention: Duration,
+    /// file with the secret webhook deliveries are signed with, callbacks are disabled if unset
+    pub webhook_secret_file: Option<Utf8PathBuf>,
+    /// maximum number of attempts to deliver a webhook
+    pub webhook_max_attempts: u32,
+    /// delay before the first webhook retry, doubled after every failed attempt
+    pub webhook_initial_backoff: Duration,
+    /// hosts callbacks may be delivered to even if they don't resolve to public addresses
+    pub webhook_allowed_hosts: Vec<String>,
+    /// chain the node must be on for `/ready` to succeed, any chain if unset
+    pub expected_chain_id: Option<String>,
+    /// certificates to serve the api over TLS with, plain http if unset
+    pub tls: Option<TlsConfig>,
+    /// body size, timeout and batch size limits of every request
+    pub limits: RequestLimits,
+    /// origins browsers may call the api from, `*` for any, none if empty
+    pub cors_allowed_origins: Vec<String>,
+    /// how long in-flight requests, syncs and jobs may take to finish on shutdown
+    pub shutdown_timeout: Duration,
 }
 
-async fn shutdown_signal() {
-    // Wait for CTRL+C signal
-    let ctrl_c = async {
-        signal::ctrl_c()
-            .await
-            .expect("failed to install Ctrl+C handler");
-    };
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            client_idle_timeout: Duration::from_secs(600),
+            sync_concurrency: 4,
+            max_concurrent_syncs: 8,
+            sync_interval: Duration::from_secs(30),
+            lookup_concurrency: 16,
+            disclosure_cache_size: 10_000,
+            auth: None,
+            rate_limit: None,
+            wallet_store: None,
+            wallet_key_file: None,
+            job_workers: 4,
+            max_queued_jobs: 1000,
+            job_dir: None,
+            job_retention: Duration::from_secs(86400),
+            webhook_secret_file: None,
+            webhook_max_attempts: 5,
+            webhook_initial_backoff: Duration::from_secs(1),
+            webhook_allowed_hosts: vec![],
+            expected_chain_id: None,
+            tls: None,
+            limits: RequestLimits::default(),
+            cors_allowed_origins: vec![],
+            shutdown_timeout: Duration::from_secs(30),
+        }
+    }
+}
 
-    let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("failed to install signal handler")
-            .recv()
-            .await;
+pub async fn start_api(url: String, listen_url: String, opts: ServerOptions) -> Result<()> {
+    log::info!("Starting API server - connecting to Penumbra gRPC at {url}");
+    log::info!("API server listening on {listen_url}");
+    if opts.auth.is_none() {
+        log::warn!("no auth config given, the disclosure endpoints are unauthenticated");
+    }
+    let state = Arc::new(AppState::new(url, &opts)?);
+    state.pool.spawn_evictor();
+    state.sync.spawn();
+    state.jobs.spawn_reaper();
+    let router = router::new(state.clone());
+    let listener = tokio::net::TcpListener::bind(listen_url)
+        .await
+        .with_context(|| "failed to create listener")?;
+    let signal = {
+        let shutdown = state.shutdown.clone();
+        let grace_period = opts.shutdown_timeout;
+        async move {
+            shutdown::signal().await;
+            log::info!(
+                "shutdown signal received, waiting up to {}s for in-flight work",
+                grace_period.as_secs()
+            );
+            shutdown.request(grace_period);
+        }
     };
-
-    // Wait for either signal
-    tokio::select! {
-        _ = ctrl_c => {},
-        _ = terminate => {},
+    match opts.tls {
+        Some(tls) => {
+            if tls.client_ca.is_some() {
+                log::info!("serving the api over tls, client certificates are required");
+            } else {
+                log::info!("serving the api over tls");
+            }
+            let listener = TlsListener::new(listener, tls)?;
+            axum::serve(listener, router)
+                .with_graceful_shutdown(signal)
+                .await?
+        }
+        None => {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(signal)
+                .await?
+        }
     }
-
-    log::info!("Shutdown signal received, starting graceful shutdown...");
-
-    // Add a small delay to allow in-flight requests to complete
-    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
-    log::info!("Server shutdown complete");
+    // requests are done, but jobs and background syncs may still be running
+    state.shutdown.wait().await;
+    log::info!("server shutdown complete");
+    Ok(())
 }
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        client::{fvk_hash, DisclosureClient},
+        error::Result,
+    },
+    penumbra_sdk_keys::FullViewingKey,
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::{Mutex, OnceCell},
+};
+
+/// Pool of long-lived [`DisclosureClient`]s keyed by the sha3 hash of the FVK
+///
+/// Keeping clients warm means repeated disclosures for the same wallet skip
+/// loading the view server and reconnecting to the node, and only need an
+/// incremental sync. Clients that have not been used within `idle_timeout`
+/// are dropped by [`ClientPool::evict_idle`].
+pub struct ClientPool {
+    url: String,
+    idle_timeout: Duration,
+    clients: Mutex<HashMap<String, PooledClient>>,
+}
+
+struct PooledClient {
+    /// initialization is shared so concurrent requests for a new FVK
+    /// don't race to open the same view database
+    client: Arc<OnceCell<DisclosureClient>>,
+    last_used: Instant,
+}
+
+impl ClientPool {
+    pub fn new(url: String, idle_timeout: Duration) -> Self {
+        Self {
+            url,
+            idle_timeout,
+            clients: Default::default(),
+        }
+    }
+
+    /// Returns the pooled client for `fvk`, initializing it if needed
+    pub async fn get(&self, fvk: &FullViewingKey) -> Result<DisclosureClient> {
+        let cell = {
+            let mut clients = self.clients.lock().await;
+            let entry = clients
+                .entry(fvk_hash(fvk))
+                .or_insert_with(|| PooledClient {
+                    client: Default::default(),
+                    last_used: Instant::now(),
+                });
+            entry.last_used = Instant::now();
+            entry.client.clone()
+        };
+        cell.get_or_try_init(|| DisclosureClient::new(&self.url, fvk))
+            .await
+            .cloned()
+    }
+
+    /// How long a client is kept without being used
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Drops every client that has been idle for longer than the idle timeout
+    pub async fn evict_idle(&self) {
+        let mut clients = self.clients.lock().await;
+        let before = clients.len();
+        clients.retain(|_, client| client.last_used.elapsed() < self.idle_timeout);
+        let evicted = before - clients.len();
+        if evicted > 0 {
+            log::info!("evicted {evicted} idle disclosure clients");
+        }
+    }
+
+    /// Drops the client of the wallet with FVK hash `hash`, unless `in_use`
+    /// returns true while the pool is locked, returning whether it was pooled
+    pub async fn evict(&self, hash: &str, in_use: impl FnOnce() -> bool) -> bool {
+        let mut clients = self.clients.lock().await;
+        !in_use() && clients.remove(hash).is_some()
+    }
+
+    /// Periodically evicts idle clients until the pool is dropped
+    pub fn spawn_evictor(self: &Arc<Self>) {
+        let pool = Arc::downgrade(self);
+        let period = self
+            .idle_timeout
+            .clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(pool) = pool.upgrade() else {
+                    return;
+                };
+                pool.evict_idle().await;
+            }
+        });
+    }
+
+    pub async fn len(&self) -> usize {
+        self.clients.lock().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
@@ -0,0 +1,232 @@
+use {
+    crate::api::server::{auth::Principal, error::ApiError, router::AppState},
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::Response,
+        Extension,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Bucket shared by every caller when the api is unauthenticated
+const ANONYMOUS: &str = "";
+
+/// How often buckets that refilled are dropped, so callers that went away don't pile up
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many requests a caller may make
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// sustained rate, in requests per minute
+    pub per_minute: u32,
+    /// requests that may be made at once after being idle
+    pub burst: u32,
+}
+
+/// Token bucket rate limits keyed by the authenticated [`Principal`]
+///
+/// Every caller gets the default limit unless their api key has one of its
+/// own. Callers without a limit are never throttled.
+///
+/// A full bucket is the same as a new one, so buckets that refilled are
+/// dropped every [`PRUNE_INTERVAL`] and the map only holds recent callers.
+pub struct RateLimiter {
+    default: Option<RateLimit>,
+    overrides: HashMap<String, RateLimit>,
+    buckets: Mutex<Buckets>,
+}
+
+struct Buckets {
+    by_principal: HashMap<String, Bucket>,
+    pruned: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Refills the bucket at `limit`'s sustained rate for the time elapsed until `now`
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate()).min(limit.capacity());
+        self.updated = now;
+    }
+}
+
+impl RateLimit {
+    /// Tokens added per second
+    fn rate(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+
+    fn capacity(&self) -> f64 {
+        f64::from(self.burst.max(1))
+    }
+}
+
+impl RateLimiter {
+    pub fn new(default: Option<RateLimit>, overrides: HashMap<String, RateLimit>) -> Self {
+        Self {
+            default,
+            overrides,
+            buckets: Mutex::new(Buckets {
+                by_principal: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    fn limit(&self, principal: &str) -> Option<&RateLimit> {
+        self.overrides.get(principal).or(self.default.as_ref())
+    }
+
+    /// Takes a request from `principal`'s bucket, returning how long to wait if it is empty
+    pub fn check(&self, principal: &str) -> Result<(), Duration> {
+        self.check_at(principal, Instant::now())
+    }
+
+    fn check_at(&self, principal: &str, now: Instant) -> Result<(), Duration> {
+        let Some(limit) = self.limit(principal) else {
+            return Ok(());
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.pruned) >= PRUNE_INTERVAL {
+            self.prune(&mut buckets, now);
+        }
+        let bucket = buckets
+            .by_principal
+            .entry(principal.to_string())
+            .or_insert_with(|| Bucket {
+                tokens: limit.capacity(),
+                updated: now,
+            });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let rate = limit.rate();
+        if rate == 0.0 {
+            return Err(Duration::from_secs(60));
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+
+    /// Drops the buckets that refilled by `now`
+    fn prune(&self, buckets: &mut Buckets, now: Instant) {
+        buckets.by_principal.retain(|principal, bucket| {
+            let Some(limit) = self.limit(principal) else {
+                return false;
+            };
+            bucket.refill(limit, now);
+            bucket.tokens < limit.capacity()
+        });
+        buckets.pruned = now;
+    }
+}
+
+/// Middleware rejecting requests from callers that exceeded their rate limit
+///
+/// Must run after [`crate::api::server::auth::require_auth`] so the caller is known.
+pub async fn limit_requests(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(limiter) = &state.rate_limiter {
+        let principal = principal
+            .as_ref()
+            .map_or(ANONYMOUS, |Extension(principal)| principal.as_str());
+        limiter.check(principal).map_err(|retry_after| {
+            log::warn!(
+                "rate limited {} {} for {principal:?}",
+                req.method(),
+                req.uri().path()
+            );
+            ApiError::RateLimited(retry_after)
+        })?;
+    }
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(
+            Some(RateLimit {
+                per_minute: 60,
+                burst: 2,
+            }),
+            HashMap::from([(
+                "key:ops".to_string(),
+                RateLimit {
+                    per_minute: 600,
+                    burst: 5,
+                },
+            )]),
+        );
+        let start = Instant::now();
+        assert!(limiter.check_at("jwt:auditor", start).is_ok());
+        assert!(limiter.check_at("jwt:auditor", start).is_ok());
+        let retry_after = limiter.check_at("jwt:auditor", start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(1));
+
+        // callers have separate buckets and overrides replace the default
+        for _ in 0..5 {
+            assert!(limiter.check_at("key:ops", start).is_ok());
+        }
+        assert!(limiter.check_at("key:ops", start).is_err());
+
+        // the bucket refills at the sustained rate, up to the burst
+        assert!(limiter
+            .check_at("jwt:auditor", start + Duration::from_secs(1))
+            .is_ok());
+        assert!(limiter
+            .check_at("jwt:auditor", start + Duration::from_secs(1))
+            .is_err());
+        let later = start + Duration::from_secs(3600);
+        assert!(limiter.check_at("jwt:auditor", later).is_ok());
+        assert!(limiter.check_at("jwt:auditor", later).is_ok());
+        assert!(limiter.check_at("jwt:auditor", later).is_err());
+
+        let unlimited = RateLimiter::new(None, HashMap::new());
+        for _ in 0..100 {
+            assert!(unlimited.check_at("jwt:auditor", start).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_prunes_full_buckets() {
+        let limiter = RateLimiter::new(
+            Some(RateLimit {
+                per_minute: 1,
+                burst: 2,
+            }),
+            HashMap::new(),
+        );
+        let start = limiter.buckets.lock().unwrap().pruned;
+        assert!(limiter.check_at("key:ops", start).is_ok());
+        assert!(limiter.check_at("key:ops", start).is_ok());
+        assert!(limiter.check_at("jwt:auditor", start).is_ok());
+
+        // by then only the auditor's bucket refilled, so only it is dropped
+        let later = start + PRUNE_INTERVAL;
+        assert!(limiter.check_at("jwt:other", later).is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        let mut principals = buckets.by_principal.keys().collect::<Vec<_>>();
+        principals.sort();
+        assert_eq!(principals, ["jwt:other", "key:ops"]);
+    }
+}
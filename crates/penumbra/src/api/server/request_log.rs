@@ -0,0 +1,161 @@
+use {
+    crate::client::fvk_hash_prefix,
+    axum::{
+        extract::{MatchedPath, Request},
+        middleware::Next,
+        response::Response,
+    },
+    http::HeaderValue,
+    penumbra_sdk_keys::FullViewingKey,
+    std::time::Instant,
+    tracing::{field, Instrument, Span},
+};
+
+/// Header carrying the id a request is logged under, echoed back in the response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request id accepted from a client, longer ids are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Middleware logging one structured line per request once it is answered
+///
+/// Requests are handled within a `request` span, so everything logged while
+/// handling one carries its id. Handlers add what they learn about a request
+/// to the span with [`record_fvk`], [`record_transactions`] and [`record_error`].
+pub async fn log_requests(
+    matched_path: Option<MatchedPath>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|id| is_valid_request_id(id))
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("uuids are valid header values")
+        });
+    req.headers_mut()
+        .insert(REQUEST_ID_HEADER, request_id.clone());
+
+    let route = matched_path
+        .as_ref()
+        .map_or("unmatched", |path| path.as_str());
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format_args!("{} {route}", req.method()),
+        otel.kind = "server",
+        request_id = request_id.to_str().unwrap_or_default(),
+        method = %req.method(),
+        route,
+        fvk_hash = field::Empty,
+        transactions = field::Empty,
+        error_type = field::Empty,
+    );
+    let mut res = next.run(req).instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(
+            status = res.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "request completed"
+        )
+    });
+    res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    res
+}
+
+fn is_valid_request_id(id: &HeaderValue) -> bool {
+    let id = id.as_bytes();
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.iter().all(u8::is_ascii_graphic)
+}
+
+/// Adds the prefix of the FVK's hash to the current request's log line
+pub fn record_fvk(fvk: &FullViewingKey) {
+    Span::current().record("fvk_hash", fvk_hash_prefix(fvk).as_str());
+}
+
+/// Adds the number of transactions a request asked for to its log line
+pub fn record_transactions(count: usize) {
+    Span::current().record("transactions", count);
+}
+
+/// Adds the type of the error a request failed with to its log line
+pub fn record_error(error_type: &str) {
+    Span::current().record("error_type", error_type);
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            api::server::{
+                error::ApiError,
+                test_util::{send, Captured, FVK},
+            },
+            client::fvk_hash,
+            error::Error,
+        },
+        axum::{body::Body, middleware, routing::post, Router},
+        std::str::FromStr,
+    };
+
+    #[tokio::test]
+    async fn test_request_log() {
+        let (captured, _guard) = Captured::json_logs();
+
+        let router = Router::new()
+            .route(
+                "/disclose/{id}",
+                post(|| async {
+                    record_fvk(&FullViewingKey::from_str(FVK).unwrap());
+                    record_transactions(3);
+                    Err::<(), _>(ApiError::from(Error::TransactionNotFound))
+                }),
+            )
+            .layer(middleware::from_fn(log_requests));
+
+        let res = send(
+            &router,
+            Request::post("/disclose/1")
+                .header(REQUEST_ID_HEADER, "client-chosen-id")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "client-chosen-id");
+
+        // invalid ids are replaced rather than logged
+        for request_id in [None, Some("x".repeat(MAX_REQUEST_ID_LEN + 1))] {
+            let mut req = Request::post("/disclose/2");
+            if let Some(request_id) = request_id {
+                req = req.header(REQUEST_ID_HEADER, request_id);
+            }
+            let res = send(&router, req.body(Body::empty()).unwrap()).await;
+            let request_id = res.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+            assert!(uuid::Uuid::parse_str(request_id).is_ok());
+        }
+
+        let logs = captured.contents();
+        let line = logs
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .find(|line| line["fields"]["message"] == "request completed")
+            .unwrap();
+        assert_eq!(line["fields"]["status"], 404);
+        assert!(line["fields"]["latency_ms"].is_u64());
+        let span = &line["span"];
+        assert_eq!(span["request_id"], "client-chosen-id");
+        assert_eq!(span["method"], "POST");
+        assert_eq!(span["route"], "/disclose/{id}");
+        assert_eq!(
+            span["fvk_hash"],
+            fvk_hash(&FullViewingKey::from_str(FVK).unwrap())[..8]
+        );
+        assert_eq!(span["transactions"], 3);
+        assert_eq!(span["error_type"], "transaction_not_found");
+        assert!(!logs.contains(FVK));
+    }
+}
@@ -1,84 +1,271 @@
 use {
-    crate::api::server::handlers,
+    crate::api::server::{
+        auth::{self, Authenticator, Principal},
+        cache::DisclosureCache,
+        handlers,
+        jobs::JobQueue,
+        limits::{self, RequestLimits},
+        metrics,
+        pool::ClientPool,
+        rate_limit::{self, RateLimiter},
+        request_log,
+        shutdown::Shutdown,
+        sync::SyncService,
+        wallets::{self, WalletStore},
+        webhooks::WebhookSender,
+        ServerOptions,
+    },
+    anyhow::{Context, Result},
     axum::{
-        routing::{get, post},
+        extract::DefaultBodyLimit,
+        middleware,
+        routing::{delete, get, post},
         Router,
     },
-    std::sync::Arc,
-    tower_http::cors::CorsLayer,
+    http::{header, HeaderName, HeaderValue, Method},
+    std::{collections::HashMap, sync::Arc},
+    tower_http::cors::{AllowOrigin, CorsLayer},
 };
 
-#[derive(Clone)]
 pub struct AppState {
     pub url: String,
+    /// chain the node must be on for the server to be ready, any chain if unset
+    pub expected_chain_id: Option<String>,
+    pub pool: Arc<ClientPool>,
+    pub sync: Arc<SyncService>,
+    pub lookup_concurrency: usize,
+    /// disclosures already generated, served without syncing the wallet
+    pub cache: DisclosureCache,
+    pub limits: RequestLimits,
+    /// origins browsers may call the api from
+    pub cors_allowed_origins: Vec<String>,
+    pub auth: Option<Authenticator>,
+    /// throttles the disclosure endpoints per caller, unlimited if unset
+    pub rate_limiter: Option<RateLimiter>,
+    pub wallets: WalletStore,
+    pub jobs: Arc<JobQueue>,
+    pub webhooks: Option<WebhookSender>,
+    /// cancels in-flight work once the server shuts down
+    pub shutdown: Shutdown,
 }
 
-pub fn new(url: String) -> Router {
+impl AppState {
+    pub fn new(url: String, opts: &ServerOptions) -> Result<Self> {
+        let pool = Arc::new(ClientPool::new(url.clone(), opts.client_idle_timeout));
+        let key = opts
+            .wallet_key_file
+            .as_deref()
+            .map(wallets::load_key)
+            .transpose()?;
+        let wallets = match (&opts.wallet_store, &key) {
+            (Some(path), Some(key)) => WalletStore::load(path.clone(), key)
+                .with_context(|| "failed to load wallet store")?,
+            (None, _) => WalletStore::in_memory(),
+            (Some(_), None) => anyhow::bail!("the wallet store requires a key file"),
+        };
+        let jobs = JobQueue::load(
+            opts.job_dir.clone(),
+            key.as_ref(),
+            opts.job_workers,
+            opts.max_queued_jobs,
+            opts.job_retention,
+        )
+        .with_context(|| "failed to load disclosure jobs")?;
+        let webhooks = opts
+            .webhook_secret_file
+            .as_ref()
+            .map(|secret_file| {
+                WebhookSender::load(
+                    secret_file,
+                    opts.webhook_max_attempts,
+                    opts.webhook_initial_backoff,
+                    opts.webhook_allowed_hosts.clone(),
+                )
+            })
+            .transpose()?;
+        let rate_limit_overrides = opts
+            .auth
+            .iter()
+            .flat_map(|auth| &auth.api_keys)
+            .filter_map(|api_key| {
+                let principal = Principal::api_key(&api_key.name);
+                Some((principal.as_str().to_string(), api_key.rate_limit?))
+            })
+            .collect::<HashMap<_, _>>();
+        let rate_limiter = (opts.rate_limit.is_some() || !rate_limit_overrides.is_empty())
+            .then(|| RateLimiter::new(opts.rate_limit, rate_limit_overrides));
+        let shutdown = Shutdown::default();
+        Ok(Self {
+            sync: Arc::new(SyncService::new(
+                pool.clone(),
+                opts.sync_concurrency,
+                opts.max_concurrent_syncs,
+                opts.sync_interval,
+                shutdown.clone(),
+            )),
+            pool,
+            url,
+            expected_chain_id: opts.expected_chain_id.clone(),
+            lookup_concurrency: opts.lookup_concurrency,
+            cache: DisclosureCache::new(opts.disclosure_cache_size),
+            limits: opts.limits.clone(),
+            cors_allowed_origins: opts.cors_allowed_origins.clone(),
+            auth: opts.auth.as_ref().map(Authenticator::new),
+            rate_limiter,
+            wallets,
+            jobs: Arc::new(jobs),
+            webhooks,
+            shutdown,
+        })
+    }
+}
+
+pub fn new(state: Arc<AppState>) -> Router {
     Router::new()
         .route(
             "/disclose/transaction",
             post(handlers::disclose_transaction),
         )
+        .route(
+            "/disclose/transaction/{transaction_hash}",
+            get(handlers::get_disclosed_transaction),
+        )
         .route(
             "/disclose/transactions",
             post(handlers::disclose_transactions),
         )
+        .route(
+            "/disclose/transactions/stream",
+            post(handlers::disclose_transactions_stream),
+        )
+        .route("/jobs/disclose", post(handlers::create_disclosure_job))
+        // only the disclosure routes registered above are rate limited
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_requests,
+        ))
+        .route("/jobs/{job_id}", get(handlers::get_disclosure_job))
+        .route("/wallets", post(handlers::register_wallet))
+        .route("/wallets/{wallet_id}", delete(handlers::revoke_wallet))
+        // only routes registered above require credentials
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
         .route("/health", get(handlers::health))
-        .with_state(Arc::new(AppState { url }))
-        .layer(
-            CorsLayer::new()
-                .allow_methods(tower_http::cors::Any)
-                .allow_origin(tower_http::cors::Any)
-                .allow_headers([
-                    http::header::CONTENT_TYPE,
-                    http::header::UPGRADE,
-                    http::header::CONNECTION,
-                    http::header::SEC_WEBSOCKET_KEY,
-                    http::header::SEC_WEBSOCKET_VERSION,
-                    http::header::SEC_WEBSOCKET_PROTOCOL,
-                ]),
+        .route("/ready", get(handlers::ready))
+        .route("/openapi.json", get(handlers::openapi_json))
+        .route("/openapi.yaml", get(handlers::openapi_yaml))
+        .route("/docs", get(handlers::docs))
+        .route("/docs/docs.js", get(handlers::docs_js))
+        .route("/docs/docs.css", get(handlers::docs_css))
+        .route("/metrics", get(handlers::metrics))
+        .layer(DefaultBodyLimit::max(state.limits.max_body_size))
+        .layer(middleware::from_fn_with_state(
+            state.limits.clone(),
+            limits::enforce_limits,
+        ))
+        .layer(middleware::from_fn(metrics::track_requests))
+        .layer(middleware::from_fn(request_log::log_requests))
+        .layer(cors(&state.cors_allowed_origins))
+        .with_state(state)
+}
+
+/// Allows browsers on `allowed_origins` to call the api, `*` allows any origin
+fn cors(allowed_origins: &[String]) -> CorsLayer {
+    let allow_origin = if allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        // origins are validated when the config is loaded
+        AllowOrigin::list(
+            allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
         )
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            HeaderName::from_static(auth::API_KEY_HEADER),
+            header::CONTENT_TYPE,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(request_log::REQUEST_ID_HEADER),
+            HeaderName::from_static(handlers::WALLET_ID_HEADER),
+        ])
+        .expose_headers([
+            header::ETAG,
+            header::LOCATION,
+            HeaderName::from_static(handlers::SYNCED_HEIGHT_HEADER),
+            HeaderName::from_static(request_log::REQUEST_ID_HEADER),
+        ])
 }
 
 #[cfg(test)]
 mod test {
     use {
         super::*,
-        axum::{body::Body, http::Request},
+        crate::{
+            api::server::{
+                auth::{ApiKeyConfig, AuthConfig, BearerConfig},
+                rate_limit::RateLimit,
+                test_util::{body_text, json_body, post_json, send, Captured, FVK},
+                webhooks,
+            },
+            client::fvk_hash,
+        },
+        axum::{body::Body, extract::State, http::Request},
         common::models::{
-            counterparty::Role, transaction::Protocol, Asset, Counterparty,
-            DisclosedTransactionResult, DisclosureRequestMultiple, DisclosureRequestSingle,
+            callback_delivery, counterparty::Role, disclosure_job::Status,
+            error::Error as CommonError, transaction::Protocol, Asset, Counterparty,
+            DisclosedTransactionResult, DisclosureJob, DisclosureRequestMultiple,
+            DisclosureRequestSingle, Transaction, Wallet, WalletRegistrationRequest,
         },
         http::StatusCode,
-        http_body_util::BodyExt,
+        penumbra_sdk_keys::FullViewingKey,
         serde_json::Value,
-        tower::{Service, ServiceExt},
+        std::{str::FromStr, time::Duration},
+        tower::ServiceExt,
     };
 
+    fn assert_no_fvk(text: &str) {
+        // check every chunk of the key data, not just the whole key
+        let key_data = &FVK["penumbrafullviewingkey1".len()..];
+        for chunk in key_data.as_bytes().windows(16) {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            assert!(!text.contains(chunk), "fvk leaked in {text}");
+        }
+    }
+
+    fn test_router() -> Router {
+        new(Arc::new(
+            AppState::new(
+                "http://localhost:8080/".to_string(),
+                &ServerOptions::default(),
+            )
+            .unwrap(),
+        ))
+    }
+
     #[tokio::test]
     async fn test_disclose_transaction() {
-        let mut router = new("http://localhost:8080/".to_string());
+        let router = test_router();
 
-        let request = Request::builder().method("POST").uri("/disclose/transaction").header("Content-Type", "application/json").body(Body::from(serde_json::to_string(&DisclosureRequestSingle {
-            full_viewing_key: "penumbrafullviewingkey1jzwnl8k7hhqnvf06m4hfdwtsyc9ucce4nq6slpvxm8l9jgse0gg676654ea865dz4mn9ez33q3ysnedcplxey5g589cx4xl0duqkzrc0gqscq".to_string(),
-            transaction_hash: "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string()
-        }).unwrap())).unwrap();
-        let res = ServiceExt::<Request<Body>>::ready(&mut router)
-            .await
-            .unwrap()
-            .call(request)
-            .await
-            .unwrap();
+        let request = post_json(
+            "/disclose/transaction",
+            &DisclosureRequestSingle {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hash:
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+            },
+        );
+        let res = send(&router, request).await;
         let status = res.status();
+        assert!(res.headers().contains_key(handlers::SYNCED_HEIGHT_HEADER));
 
-        let res: DisclosedTransactionResult =
-            serde_json::from_slice(&res.into_body().collect().await.unwrap().to_bytes()).unwrap();
-        assert!(res.disclosure_errors.is_none());
-
-        let Some(txns) = res.disclosure_transactions else {
-            panic!("disclosure_transactions should not be None");
-        };
-        let tx_info = &txns.transactions[0];
+        let tx_info: Transaction = json_body(res).await;
         assert_eq!(
             tx_info.transaction_hash,
             "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf"
@@ -111,22 +298,25 @@ mod test {
 
     #[tokio::test]
     async fn test_disclose_transactions() {
-        let mut router = new("http://localhost:8080/".to_string());
+        let router = test_router();
 
-        let request = Request::builder().method("POST").uri("/disclose/transactions").header("Content-Type", "application/json").body(Body::from(serde_json::to_string(&DisclosureRequestMultiple {
-            full_viewing_key: "penumbrafullviewingkey1jzwnl8k7hhqnvf06m4hfdwtsyc9ucce4nq6slpvxm8l9jgse0gg676654ea865dz4mn9ez33q3ysnedcplxey5g589cx4xl0duqkzrc0gqscq".to_string(),
-            transaction_hashes: vec!["c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(), "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b2517fffffffffccff".to_string()]
-        }).unwrap())).unwrap();
-        let res = ServiceExt::<Request<Body>>::ready(&mut router)
-            .await
-            .unwrap()
-            .call(request)
-            .await
-            .unwrap();
+        let request = post_json(
+            "/disclose/transactions",
+            &DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b2517fffffffffccff".to_string(),
+                ],
+                callback_url: None,
+            },
+        );
+        let res = send(&router, request).await;
         let status = res.status();
+        assert!(res.headers().contains_key(handlers::SYNCED_HEIGHT_HEADER));
 
-        let res: DisclosedTransactionResult =
-            serde_json::from_slice(&res.into_body().collect().await.unwrap().to_bytes()).unwrap();
+        let res: DisclosedTransactionResult = json_body(res).await;
         let Some(disclosure_errors) = res.disclosure_errors else {
             panic!("there should be at least one disclosure error");
         };
@@ -135,6 +325,14 @@ mod test {
             disclosure_errors.errors[0].transaction_hash,
             "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b2517fffffffffccff"
         );
+        assert_eq!(
+            disclosure_errors.errors[0].error_types,
+            vec!["transaction_not_found".to_string()]
+        );
+        assert_eq!(
+            disclosure_errors.errors[0].error.code,
+            "transaction_not_found"
+        );
 
         let Some(txns) = res.disclosure_transactions else {
             panic!("disclosure_transactions should not be None");
@@ -169,28 +367,1053 @@ mod test {
 
         assert_eq!(status, StatusCode::OK);
     }
+
     #[tokio::test]
-    async fn test_health() {
-        let mut router = new("http://localhost:8080/".to_string());
-        let request = Request::builder()
-            .method("GET")
-            .uri("/health")
-            .body(Body::from(""))
-            .unwrap();
-        let res = ServiceExt::<Request<Body>>::ready(&mut router)
-            .await
+    async fn test_disclose_transaction_invalid_fvk() {
+        let router = test_router();
+
+        let request = post_json(
+            "/disclose/transaction",
+            &DisclosureRequestSingle {
+                full_viewing_key: Some("not a viewing key".to_string()),
+                wallet_id: None,
+                transaction_hash:
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "invalid_fvk");
+    }
+
+    #[tokio::test]
+    async fn test_auth_required() {
+        let router = new(Arc::new(
+            AppState::new(
+                "http://localhost:8080/".to_string(),
+                &ServerOptions {
+                    auth: Some(AuthConfig {
+                        api_keys: vec![ApiKeyConfig {
+                            name: "ops".to_string(),
+                            key: "test-api-key".to_string(),
+                            rate_limit: None,
+                        }],
+                        bearer: None,
+                    }),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        let body = serde_json::to_string(&DisclosureRequestSingle {
+            full_viewing_key: Some("not a viewing key".to_string()),
+            wallet_id: None,
+            transaction_hash: "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf"
+                .to_string(),
+        })
+        .unwrap();
+
+        for (api_key, status) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (Some("wrong-key"), StatusCode::UNAUTHORIZED),
+            // authenticated requests reach the handler, which rejects the fvk
+            (Some("test-api-key"), StatusCode::BAD_REQUEST),
+        ] {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/disclose/transaction")
+                .header("Content-Type", "application/json");
+            if let Some(api_key) = api_key {
+                request = request.header(auth::API_KEY_HEADER, api_key);
+            }
+            let res = send(&router, request.body(Body::from(body.clone())).unwrap()).await;
+            assert_eq!(res.status(), status);
+            if status == StatusCode::UNAUTHORIZED {
+                let err: CommonError = json_body(res).await;
+                assert_eq!(err.code, "unauthorized");
+            }
+        }
+
+        // health checks stay unauthenticated
+        let res = send(
+            &router,
+            Request::get("/health").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_registration() {
+        let state = Arc::new(
+            AppState::new(
+                "http://localhost:8080/".to_string(),
+                &ServerOptions::default(),
+            )
+            .unwrap(),
+        );
+        let router = new(state.clone());
+        let tx_hash = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+
+        let request = post_json(
+            "/wallets",
+            &WalletRegistrationRequest {
+                full_viewing_key: FVK.to_string(),
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let wallet: Wallet = json_body(res).await;
+        assert!(!wallet
+            .wallet_id
+            .contains(&FVK["penumbrafullviewingkey1".len()..][..16]));
+
+        // the fvk and the handle are mutually exclusive
+        let request = post_json(
+            "/disclose/transaction",
+            &DisclosureRequestSingle {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: Some(wallet.wallet_id.clone()),
+                transaction_hash: tx_hash.to_string(),
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "invalid_request");
+
+        let fvk_hash = fvk_hash(&FullViewingKey::from_str(FVK).unwrap());
+        let transaction = Transaction::new(
+            tx_hash.to_string(),
+            Protocol::Penumbra,
+            "penumbra-1".to_string(),
+            vec![],
+            "1745289093".to_string(),
+        );
+        state.cache.insert(&fvk_hash, tx_hash, &transaction, 42);
+        let revoke = || {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/wallets/{}", wallet.wallet_id))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let res = send(&router, revoke()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        // the wallet's disclosures aren't served from the cache anymore
+        assert!(state.cache.get(&fvk_hash, tx_hash).is_none());
+        let res = send(&router, revoke()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // revoked handles can no longer be used for disclosures
+        let request = post_json(
+            "/disclose/transactions",
+            &DisclosureRequestMultiple {
+                full_viewing_key: None,
+                wallet_id: Some(wallet.wallet_id.clone()),
+                transaction_hashes: vec![tx_hash.to_string()],
+                callback_url: None,
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "wallet_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_disclosure_job_failure() {
+        // the node is unreachable, so the job is accepted but fails in the background
+        let router = new(Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        ));
+        let request = post_json(
+            "/jobs/disclose",
+            &DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+                ],
+                callback_url: None,
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job: DisclosureJob = json_body(res).await;
+        assert_eq!(job.status, Status::Queued);
+
+        let job = loop {
+            let request = Request::get(format!("/jobs/{}", job.job_id))
+                .body(Body::empty())
+                .unwrap();
+            let res = send(&router, request).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let job: DisclosureJob = json_body(res).await;
+            if job.status == Status::Failed {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(job.error.unwrap().code, "node_unavailable");
+        assert!(job.result.is_none());
+
+        let request = Request::get("/jobs/unknown").body(Body::empty()).unwrap();
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request_stops_sync() {
+        // a node that accepts connections but never answers, so the sync hangs
+        let node = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let state = Arc::new(
+            AppState::new(
+                format!("http://{}", node.local_addr().unwrap()),
+                &ServerOptions::default(),
+            )
+            .unwrap(),
+        );
+        let request = post_json(
+            "/disclose/transaction",
+            &DisclosureRequestSingle {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hash:
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+            },
+        );
+        let handler = tokio::spawn(new(state.clone()).oneshot(request));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.pool.is_empty().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // the client of a wallet that never finished syncing is evicted with its view server
+        handler.abort();
+        assert!(handler.await.unwrap_err().is_cancelled());
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !state.pool.is_empty().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(state.sync.sync_lags().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disclose_transactions_stream() {
+        // the node is unreachable, so the stream ends with the sync failure
+        let router = new(Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        ));
+        let stream_request = |fvk: &str| {
+            post_json(
+                "/disclose/transactions/stream",
+                &DisclosureRequestMultiple {
+                    full_viewing_key: Some(fvk.to_string()),
+                    wallet_id: None,
+                    transaction_hashes: vec![
+                        "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf"
+                            .to_string(),
+                    ],
+                    callback_url: None,
+                },
+            )
+        };
+
+        // invalid requests are rejected before the stream starts
+        let res = send(&router, stream_request("not a viewing key")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = send(&router, stream_request(FVK)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let body = body_text(res).await;
+        let data = body
+            .strip_prefix(&format!("event: {}\ndata: ", handlers::ERROR_EVENT))
+            .unwrap_or_else(|| panic!("expected an error event, got {body}"));
+        let err: CommonError = serde_json::from_str(data.trim()).unwrap();
+        assert_eq!(err.code, "node_unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_disclosure_job_callback() {
+        type Received = Arc<std::sync::Mutex<Vec<(http::HeaderMap, Vec<u8>)>>>;
+        let received = Received::default();
+        let receiver = Router::new()
+            .route(
+                "/callback",
+                post(
+                    |State(received): State<Received>,
+                     headers: http::HeaderMap,
+                     body: axum::body::Bytes| async move {
+                        received.lock().unwrap().push((headers, body.to_vec()));
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let callback_url = format!("http://{}/callback", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let secret_file = camino::Utf8PathBuf::from_path_buf(std::env::temp_dir())
             .unwrap()
-            .call(request)
-            .await
-            .unwrap();
-        let status = res.status();
-        assert_eq!(status, StatusCode::OK);
+            .join(format!("webhook-{}.secret", std::process::id()));
+        std::fs::write(&secret_file, "webhook secret").unwrap();
+        // the node is unreachable, so the job fails and its error is delivered
+        let router = new(Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    webhook_secret_file: Some(secret_file.clone()),
+                    webhook_allowed_hosts: vec!["127.0.0.1".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        std::fs::remove_file(secret_file).unwrap();
+
+        let request = post_json(
+            "/jobs/disclose",
+            &DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![
+                    "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf".to_string(),
+                ],
+                callback_url: Some(callback_url.clone()),
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job: DisclosureJob = json_body(res).await;
+
+        let callback = loop {
+            let request = Request::get(format!("/jobs/{}", job.job_id))
+                .body(Body::empty())
+                .unwrap();
+            let res = send(&router, request).await;
+            let job: DisclosureJob = json_body(res).await;
+            let callback = job.callback.unwrap();
+            if callback.status != callback_delivery::Status::Pending {
+                break callback;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(callback.status, callback_delivery::Status::Delivered);
+        assert_eq!(callback.url, callback_url);
+        assert_eq!(callback.attempts.len(), 1);
+        assert_eq!(callback.attempts[0].status_code, Some(200));
 
-        let body_bytes = res.into_body().collect().await.unwrap().to_bytes();
-        let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(headers[webhooks::JOB_ID_HEADER], job.job_id.as_str());
+        assert_eq!(headers[webhooks::JOB_STATUS_HEADER], "failed");
+        assert!(webhooks::verify_signature(
+            b"webhook secret",
+            headers[webhooks::SIGNATURE_HEADER].to_str().unwrap(),
+            body,
+            std::time::Duration::from_secs(60),
+        ));
+        let err: CommonError = serde_json::from_slice(body).unwrap();
+        assert_eq!(err.code, "node_unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_callback_url_requires_webhooks() {
+        let router = test_router();
+        let request = post_json(
+            "/jobs/disclose",
+            &DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![],
+                callback_url: Some("http://127.0.0.1:1/callback".to_string()),
+            },
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn test_errors_never_leak_fvk() {
+        // `log` records are forwarded to the subscriber of the thread they're
+        // logged on, so only this test's records are captured
+        let _ = tracing_log::LogTracer::init();
+        let (captured, _guard) = Captured::json_logs();
+        // the node is unreachable, so even a valid fvk fails while initializing the client
+        let router = new(Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        ));
+        let tx_hash = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+        let corrupted_fvk = format!("{}x", &FVK[..FVK.len() - 1]);
+        let mut job_ids = Vec::new();
+
+        for full_viewing_key in [FVK.to_string(), corrupted_fvk] {
+            let requests = [
+                post_json(
+                    "/disclose/transaction",
+                    &DisclosureRequestSingle {
+                        full_viewing_key: Some(full_viewing_key.clone()),
+                        wallet_id: None,
+                        transaction_hash: tx_hash.to_string(),
+                    },
+                ),
+                post_json(
+                    "/disclose/transactions",
+                    &DisclosureRequestMultiple {
+                        full_viewing_key: Some(full_viewing_key.clone()),
+                        wallet_id: None,
+                        transaction_hashes: vec![tx_hash.to_string()],
+                        callback_url: None,
+                    },
+                ),
+            ];
+            for request in requests {
+                let res = send(&router, request).await;
+                assert!(!res.status().is_success());
+                assert_no_fvk(&body_text(res).await);
+            }
+
+            // a key pasted as a transaction hash is malformed input that's echoed back
+            let request = DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec![full_viewing_key.clone()],
+                callback_url: None,
+            };
+            for uri in [
+                "/disclose/transactions",
+                "/disclose/transactions/stream",
+                "/jobs/disclose",
+            ] {
+                let res = send(&router, post_json(uri, &request)).await;
+                let body = body_text(res).await;
+                assert_no_fvk(&body);
+                match serde_json::from_str::<DisclosureJob>(&body) {
+                    Ok(job) => job_ids.push(job.job_id),
+                    Err(_) => assert!(body.contains("[redacted fvk]"), "{body}"),
+                }
+            }
+        }
+        // jobs echo the redacted hashes in their result once they're done
+        assert_eq!(job_ids.len(), 2);
+        for job_id in job_ids {
+            let job = loop {
+                let request = Request::get(format!("/jobs/{job_id}"))
+                    .body(Body::empty())
+                    .unwrap();
+                let job: DisclosureJob = json_body(send(&router, request).await).await;
+                if matches!(job.status, Status::Completed | Status::Failed) {
+                    break job;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            };
+            let job = serde_json::to_string(&job).unwrap();
+            assert!(job.contains("[redacted fvk]"), "{job}");
+            assert_no_fvk(&job);
+        }
+
+        let logs = captured.contents();
+        // every failed request is logged, so an empty capture means logs weren't checked
+        assert!(logs.matches("request failed with").count() >= 4);
+        assert_no_fvk(&logs);
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let router = test_router();
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let json: Value = json_body(res).await;
 
         assert_eq!(json["status"], "ok");
         assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
         assert!(json["timestamp"].is_string());
     }
+
+    #[test]
+    fn test_generated_client_matches_openapi() {
+        // the generated client has one typed error per documented 4xx and 5xx
+        // response, so it has to be regenerated rather than edited by hand
+        let client = include_str!("../../../../common/src/apis/default_api.rs");
+        let spec: Value = serde_json::from_str(handlers::OPENAPI_JSON).unwrap();
+        for operations in spec["paths"].as_object().unwrap().values() {
+            for operation in operations.as_object().unwrap().values() {
+                let id = operation["operationId"].as_str().unwrap();
+                let error = format!("pub enum {}{}Error {{", id[..1].to_uppercase(), &id[1..]);
+                let Some(start) = client.find(&error) else {
+                    continue;
+                };
+                let variants = client[start..]
+                    .lines()
+                    .skip(1)
+                    .take_while(|line| line.trim() != "}")
+                    .filter_map(|line| line.trim().strip_prefix("Status"))
+                    .map(|variant| variant[..3].to_string())
+                    .collect::<Vec<_>>();
+                let documented = operation["responses"]
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .filter(|status| status.starts_with(['4', '5']))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                assert_eq!(variants, documented, "{id} errors differ from openapi.yaml");
+            }
+        }
+    }
+
+    /// Routes serving the documentation, which isn't described in itself
+    const UNDOCUMENTED_ROUTES: [&str; 5] = [
+        "/openapi.json",
+        "/openapi.yaml",
+        "/docs",
+        "/docs/docs.js",
+        "/docs/docs.css",
+    ];
+
+    #[tokio::test]
+    async fn test_openapi_matches_routes() {
+        let router = test_router();
+        let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let spec: Value = json_body(res).await;
+        let paths = spec["paths"].as_object().unwrap();
+
+        // every documented operation is routed, unrouted requests get an empty 404 or a 405
+        for (path, operations) in paths {
+            for method in operations.as_object().unwrap().keys() {
+                let uri = path.replace(['{', '}'], "");
+                let request = Request::builder()
+                    .method(method.to_uppercase().as_str())
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let res = send(&router, request).await;
+                let status = res.status();
+                let body = body_text(res).await;
+                assert!(
+                    status != StatusCode::METHOD_NOT_ALLOWED
+                        && !(status == StatusCode::NOT_FOUND && body.is_empty()),
+                    "{method} {path} is documented but not routed"
+                );
+            }
+        }
+
+        // and every route is documented, except the ones serving the documentation itself
+        let source = include_str!("router.rs");
+        let source = &source[source.find("\npub fn new(").unwrap()..];
+        let source = &source[..source.find("\n}\n").unwrap()];
+        let mut routed = source
+            .split(".route(")
+            .skip(1)
+            .map(|route| {
+                let path = route.split('"').nth(1).unwrap();
+                let method = route.split("(handlers::").next().unwrap();
+                let method = method.rsplit([' ', '\n', ',']).next().unwrap();
+                (method.to_string(), path.to_string())
+            })
+            .filter(|(_, path)| !UNDOCUMENTED_ROUTES.contains(&path.as_str()))
+            .collect::<Vec<_>>();
+        let mut documented = paths
+            .iter()
+            .flat_map(|(path, operations)| {
+                operations
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .map(|method| (method.clone(), path.clone()))
+            })
+            .collect::<Vec<_>>();
+        // the spec names path parameters in camel case
+        let normalize = |routes: &mut Vec<(String, String)>| {
+            for (_, path) in routes.iter_mut() {
+                *path = path
+                    .split('/')
+                    .map(|segment| {
+                        if segment.starts_with('{') {
+                            "{}"
+                        } else {
+                            segment
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+            }
+            routes.sort();
+        };
+        normalize(&mut routed);
+        normalize(&mut documented);
+        assert_eq!(routed, documented, "routes differ from openapi.yaml");
+
+        // the json document is generated from the yaml one, so it must not go stale
+        let mut yaml_paths = handlers::OPENAPI_YAML
+            .lines()
+            .filter_map(|line| line.strip_prefix("  /"))
+            .filter_map(|line| line.strip_suffix(':'))
+            .map(|path| format!("/{path}"))
+            .collect::<Vec<_>>();
+        yaml_paths.sort();
+        assert_eq!(yaml_paths, paths.keys().cloned().collect::<Vec<_>>());
+        let yaml_operations = handlers::OPENAPI_YAML
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("operationId: "))
+            .count();
+        let json_operations = handlers::OPENAPI_JSON.matches("\"operationId\"").count();
+        assert_eq!(yaml_operations, json_operations);
+
+        for (uri, content_type) in [
+            ("/openapi.yaml", "application/yaml"),
+            ("/docs", "text/html; charset=utf-8"),
+            ("/docs/docs.js", "text/javascript"),
+            ("/docs/docs.css", "text/css"),
+        ] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            let res = send(&router, request).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers()[header::CONTENT_TYPE], content_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let router = test_router();
+        for uri in ["/health", "/jobs/unknown", "/metrics"] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            let res = send(&router, request).await;
+            assert_ne!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            if uri != "/metrics" {
+                continue;
+            }
+            let body = body_text(res).await;
+            assert!(body.contains(
+                "disclosure_http_requests_total{method=\"GET\",route=\"/health\",status=\"200\"}"
+            ));
+            // requests are labeled by route, not by the requested path
+            assert!(body.contains(
+                "disclosure_http_requests_total{method=\"GET\",route=\"/jobs/{job_id}\",status=\"404\"}"
+            ));
+            assert!(body.contains("disclosure_client_pool_size 0\n"));
+            assert!(body.contains("disclosure_registered_wallets 0\n"));
+            assert!(!body.contains("wallet="));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ready_node_unavailable() {
+        let router = new(Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        ));
+        let res = send(&router, Request::get("/ready").body(Body::empty()).unwrap()).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json: Value = json_body(res).await;
+        assert_eq!(json["status"], "unavailable");
+        assert_eq!(json["reason"], "failed to connect to node");
+        assert!(json.get("chainId").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_too_many_transactions() {
+        let router = new(Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    limits: RequestLimits {
+                        max_transaction_hashes: 2,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        let request = DisclosureRequestMultiple {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hashes: vec!["00".repeat(32); 3],
+            callback_url: None,
+        };
+        for uri in [
+            "/disclose/transactions",
+            "/disclose/transactions/stream",
+            "/jobs/disclose",
+        ] {
+            let res = send(&router, post_json(uri, &request)).await;
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{uri}");
+            let err: CommonError = json_body(res).await;
+            assert_eq!(err.code, "too_many_transactions");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_hashes() {
+        // the node is unreachable, so anything that syncs fails with a 503
+        let router = new(Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        ));
+
+        let request = DisclosureRequestSingle {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hash: "0xabc123".to_string(),
+        };
+        let res = send(&router, post_json("/disclose/transaction", &request)).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "invalid_hash");
+
+        // duplicates are collapsed and every hash fails without a sync
+        let request = DisclosureRequestMultiple {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hashes: vec![
+                "0xabc123".to_string(),
+                "zz".to_string(),
+                "0xabc123".to_string(),
+            ],
+            callback_url: None,
+        };
+        let res = send(&router, post_json("/disclose/transactions", &request)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(handlers::SYNCED_HEIGHT_HEADER));
+        let res: DisclosedTransactionResult = json_body(res).await;
+        assert!(res.disclosure_transactions.is_none());
+        let errors = res.disclosure_errors.unwrap().errors;
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| (err.transaction_hash.as_str(), err.error_types[0].as_str()))
+                .collect::<Vec<_>>(),
+            [("0xabc123", "invalid_hash"), ("zz", "invalid_hash")]
+        );
+
+        let res = send(
+            &router,
+            post_json("/disclose/transactions/stream", &request),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let events = body_text(res).await;
+        assert_eq!(events.matches("event: disclosureError").count(), 2);
+        assert!(events.contains(r#"data: {"syncedHeight":0,"disclosed":0,"failed":2}"#));
+    }
+
+    #[tokio::test]
+    async fn test_disclosure_cache() {
+        // the node is unreachable, so only cached disclosures can succeed
+        let state = Arc::new(
+            AppState::new("http://127.0.0.1:1".to_string(), &ServerOptions::default()).unwrap(),
+        );
+        let router = new(state.clone());
+        let tx_hash = "ab".repeat(32);
+        let transaction = Transaction::new(
+            tx_hash.clone(),
+            Protocol::Penumbra,
+            "penumbra-1".to_string(),
+            vec![],
+            "1745289093".to_string(),
+        );
+        let wallet = fvk_hash(&FullViewingKey::from_str(FVK).unwrap());
+        state.cache.insert(&wallet, &tx_hash, &transaction, 42);
+        let if_none_match = |mut req: Request<Body>, etag: &HeaderValue| {
+            req.headers_mut()
+                .insert(header::IF_NONE_MATCH, etag.clone());
+            req
+        };
+
+        let request = DisclosureRequestSingle {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hash: format!("0x{}", tx_hash.to_uppercase()),
+        };
+        let res = send(&router, post_json("/disclose/transaction", &request)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[handlers::SYNCED_HEIGHT_HEADER], "42");
+        let etag = res.headers()[header::ETAG].clone();
+        let disclosed: Transaction = json_body(res).await;
+        assert_eq!(disclosed, transaction);
+
+        // only a GET is answered with 304, other methods fail the precondition
+        let res = send(
+            &router,
+            if_none_match(post_json("/disclose/transaction", &request), &etag),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "precondition_failed");
+
+        // a client that already has the disclosure doesn't get it again
+        let wallet_id = state
+            .wallets
+            .register(&FullViewingKey::from_str(FVK).unwrap())
+            .await
+            .unwrap();
+        let get = |etag: Option<&HeaderValue>| {
+            let mut req = Request::get(format!("/disclose/transaction/{tx_hash}"))
+                .header(handlers::WALLET_ID_HEADER, &wallet_id);
+            if let Some(etag) = etag {
+                req = req.header(header::IF_NONE_MATCH, etag);
+            }
+            req.body(Body::empty()).unwrap()
+        };
+        let res = send(&router, get(None)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::ETAG], etag);
+        assert_eq!(res.headers()[header::VARY], handlers::WALLET_ID_HEADER);
+        let res = send(&router, get(Some(&etag))).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], etag);
+        assert_eq!(res.headers()[header::VARY], handlers::WALLET_ID_HEADER);
+        assert_eq!(res.headers()[handlers::SYNCED_HEIGHT_HEADER], "42");
+        assert!(body_text(res).await.is_empty());
+        // the handle is only accepted in the header, never in the URL
+        let res = send(
+            &router,
+            Request::get(format!(
+                "/disclose/transaction/{tx_hash}?walletId={wallet_id}"
+            ))
+            .body(Body::empty())
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let request = DisclosureRequestMultiple {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hashes: vec![tx_hash.clone()],
+            callback_url: None,
+        };
+        let res = send(&router, post_json("/disclose/transactions", &request)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[handlers::SYNCED_HEIGHT_HEADER], "42");
+        let etag = res.headers()[header::ETAG].clone();
+        let res = send(
+            &router,
+            if_none_match(post_json("/disclose/transactions", &request), &etag),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        let res = send(
+            &router,
+            post_json("/disclose/transactions/stream", &request),
+        )
+        .await;
+        let events = body_text(res).await;
+        assert_eq!(events.matches("event: transaction").count(), 1);
+        assert!(events.contains(r#"data: {"syncedHeight":42,"disclosed":1,"failed":0}"#));
+
+        // anything that isn't cached still needs the wallet to sync
+        let request = DisclosureRequestMultiple {
+            full_viewing_key: Some(FVK.to_string()),
+            wallet_id: None,
+            transaction_hashes: vec![tx_hash, "cd".repeat(32)],
+            callback_url: None,
+        };
+        let res = send(&router, post_json("/disclose/transactions", &request)).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_cors() {
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/disclose/transactions")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // no cross-origin access unless origins are configured
+        let res = send(&test_router(), preflight("https://evil.example.com")).await;
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let router = new(Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    cors_allowed_origins: vec!["https://app.example.com".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        let res = send(&router, preflight("https://app.example.com")).await;
+        assert_eq!(
+            res.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        let res = send(&router, preflight("https://evil.example.com")).await;
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let router = new(Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    auth: Some(AuthConfig {
+                        api_keys: vec![
+                            ApiKeyConfig {
+                                name: "ops".to_string(),
+                                key: "ops-api-key".to_string(),
+                                rate_limit: Some(RateLimit {
+                                    per_minute: 1,
+                                    burst: 1,
+                                }),
+                            },
+                            ApiKeyConfig {
+                                name: "auditor".to_string(),
+                                key: "auditor-api-key".to_string(),
+                                rate_limit: None,
+                            },
+                        ],
+                        bearer: None,
+                    }),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        let request = |uri: &str, api_key: &str| {
+            Request::post(uri)
+                .header("Content-Type", "application/json")
+                .header(auth::API_KEY_HEADER, api_key)
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        // the invalid request still takes a token before the handler rejects it
+        let res = send(&router, request("/disclose/transactions", "ops-api-key")).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let res = send(&router, request("/jobs/disclose", "ops-api-key")).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "60");
+        let err: CommonError = json_body(res).await;
+        assert_eq!(err.code, "rate_limited");
+
+        // other keys and routes aren't affected
+        let res = send(
+            &router,
+            request("/disclose/transactions", "auditor-api-key"),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let res = send(
+            &router,
+            Request::get("/jobs/unknown")
+                .header(auth::API_KEY_HEADER, "ops-api-key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_principals_are_namespaced() {
+        let state = Arc::new(
+            AppState::new(
+                "http://127.0.0.1:1".to_string(),
+                &ServerOptions {
+                    auth: Some(AuthConfig {
+                        api_keys: vec![ApiKeyConfig {
+                            name: "ops".to_string(),
+                            key: "ops-api-key".to_string(),
+                            rate_limit: Some(RateLimit {
+                                per_minute: 1,
+                                burst: 1,
+                            }),
+                        }],
+                        bearer: Some(BearerConfig {
+                            secret: "0123456789abcdef0123456789abcdef".to_string(),
+                        }),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        );
+        let router = new(state.clone());
+        // a token whose subject is the name of the api key
+        let token = state
+            .auth
+            .as_ref()
+            .unwrap()
+            .issue_token("ops", Duration::from_secs(60))
+            .unwrap();
+        let bearer = format!("Bearer {token}");
+
+        let mut request = post_json(
+            "/jobs/disclose",
+            &DisclosureRequestMultiple {
+                full_viewing_key: Some(FVK.to_string()),
+                wallet_id: None,
+                transaction_hashes: vec!["0xabc123".to_string()],
+                callback_url: None,
+            },
+        );
+        request.headers_mut().insert(
+            auth::API_KEY_HEADER,
+            HeaderValue::from_static("ops-api-key"),
+        );
+        let res = send(&router, request).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        let job: DisclosureJob = json_body(res).await;
+
+        // the token neither shares the key's rate limit nor sees its jobs
+        let res = send(
+            &router,
+            Request::get(format!("/jobs/{}", job.job_id))
+                .header(header::AUTHORIZATION, &bearer)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_ready() {
+        let router = new(Arc::new(
+            AppState::new(
+                "http://localhost:8080/".to_string(),
+                &ServerOptions {
+                    expected_chain_id: Some("penumbra-testnet-phobos-x3b26d34a".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        ));
+        let res = send(&router, Request::get("/ready").body(Body::empty()).unwrap()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let json: Value = json_body(res).await;
+        assert_eq!(json["chainId"], "penumbra-testnet-phobos-x3b26d34a");
+        assert!(json["latestHeight"].as_u64().unwrap() > 0);
+    }
 }
@@ -0,0 +1,119 @@
+use {
+    std::{future::Future, time::Duration},
+    tokio::signal,
+    tokio_util::{sync::CancellationToken, task::TaskTracker},
+};
+
+/// Coordinates the graceful shutdown of the work running in the background
+///
+/// Once shutdown is requested no new background syncs are started, and the
+/// work still running gets a grace period to finish. After that, every token
+/// handed out by [`Shutdown::token`] is cancelled so syncs and lookups stop
+/// at the next node query. Syncs are persisted to the view database as they
+/// go, so a cancelled sync resumes where it stopped after a restart.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: CancellationToken,
+    cancel: CancellationToken,
+    tasks: TaskTracker,
+}
+
+impl Shutdown {
+    /// Token cancelled once the grace period is over, to pass to a single request or task
+    pub fn token(&self) -> CancellationToken {
+        self.cancel.child_token()
+    }
+
+    /// Resolves once shutdown was requested
+    pub async fn requested(&self) {
+        self.requested.cancelled().await
+    }
+
+    /// Spawns a task the shutdown waits for
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(task);
+    }
+
+    /// Starts shutting down, cancelling whatever is still running after `grace_period`
+    pub fn request(&self, grace_period: Duration) {
+        self.requested.cancel();
+        let cancel = self.cancel.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(grace_period) => {
+                    log::warn!("shutdown grace period is over, cancelling in-flight work");
+                    cancel.cancel();
+                }
+                _ = cancel.cancelled() => {}
+            }
+        });
+    }
+
+    /// Waits for every spawned task to finish
+    pub async fn wait(&self) {
+        self.tasks.close();
+        self.tasks.wait().await;
+        self.cancel.cancel();
+    }
+}
+
+/// Resolves once the process is asked to stop with Ctrl+C or SIGTERM
+pub async fn signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        tokio::time::Instant,
+    };
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let shutdown = Shutdown::default();
+        let finished = Arc::new(AtomicBool::new(false));
+
+        // work that finishes within the grace period is waited for
+        shutdown.spawn({
+            let finished = finished.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                finished.store(true, Ordering::SeqCst);
+            }
+        });
+        // while work that doesn't is cancelled once it's over
+        let token = shutdown.token();
+        shutdown.spawn(async move { token.cancelled().await });
+
+        let started = Instant::now();
+        shutdown.request(Duration::from_millis(200));
+        shutdown.requested().await;
+        shutdown.wait().await;
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(finished.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,542 @@
+use {
+    crate::{
+        api::server::{error::ApiError, pool::ClientPool, shutdown::Shutdown},
+        client::{fvk_hash, fvk_hash_prefix, DisclosureClient},
+        error::{Error, Result},
+    },
+    futures::StreamExt,
+    penumbra_sdk_keys::FullViewingKey,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore},
+    tokio_util::sync::CancellationToken,
+};
+
+/// Progress of a wallet's sync, as reported by [`SyncService::client_with_progress`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncProgress {
+    /// every sync slot is taken, the sync starts once one frees up
+    Queued,
+    /// blocks synced so far, as in [`DisclosureClient::sync_with_progress`]
+    Syncing {
+        synced_height: u64,
+        latest_height: u64,
+    },
+}
+
+/// Keeps registered wallets continuously synced in the background
+///
+/// A wallet is registered the first time it is used for a disclosure. After
+/// its initial sync, requests are served from the view as of the last
+/// background sync instead of syncing on the request path.
+///
+/// At most `max_syncs` syncs run at once, whether inline or in the
+/// background, so a burst of new wallets can't overwhelm the node.
+///
+/// Wallets that weren't used for a disclosure within the client pool's idle
+/// timeout are unregistered, so they stop being synced and their client can
+/// be evicted from the pool.
+///
+/// Syncs and the clients handed out are cancelled once the server shuts down.
+/// If every sync of an unregistered wallet is cancelled or dropped before it
+/// completes, the wallet's client is evicted so its view server stops syncing.
+pub struct SyncService {
+    pool: Arc<ClientPool>,
+    concurrency: usize,
+    interval: Duration,
+    shutdown: Shutdown,
+    syncs: Arc<Semaphore>,
+    wallets: Arc<RwLock<HashMap<String, Wallet>>>,
+    /// FVK hashes of wallets whose sync was queued by [`SyncService::client`]
+    queued: Mutex<HashSet<String>>,
+    /// number of syncs in flight by FVK hash
+    syncing: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+struct Wallet {
+    fvk: FullViewingKey,
+    synced_height: u64,
+    /// latest block height the node reported during the wallet's last sync
+    latest_height: u64,
+    /// last time the wallet was used for a disclosure, background syncs don't count
+    last_used: Instant,
+}
+
+impl SyncService {
+    pub fn new(
+        pool: Arc<ClientPool>,
+        concurrency: usize,
+        max_syncs: usize,
+        interval: Duration,
+        shutdown: Shutdown,
+    ) -> Self {
+        Self {
+            pool,
+            concurrency: concurrency.max(1),
+            interval,
+            shutdown,
+            syncs: Arc::new(Semaphore::new(max_syncs.max(1))),
+            wallets: Default::default(),
+            queued: Default::default(),
+            syncing: Default::default(),
+        }
+    }
+
+    /// Returns a client for `fvk` along with the height its view is synced to.
+    ///
+    /// Registered wallets are returned immediately, while unregistered wallets
+    /// are synced inline and then registered for background syncing. If every
+    /// sync slot is taken, the sync is queued in the background instead and
+    /// [`ApiError::SyncQueued`] is returned, so the caller can retry once it's done.
+    pub async fn client(
+        self: &Arc<Self>,
+        fvk: &FullViewingKey,
+    ) -> Result<(DisclosureClient, u64), ApiError> {
+        let cancel = self.shutdown.token();
+        if let Some(height) = self.synced_height(fvk).await {
+            return Ok((self.pool.get(fvk).await?.with_cancellation(cancel), height));
+        }
+        let Ok(permit) = self.syncs.clone().try_acquire_owned() else {
+            self.queue_sync(fvk);
+            return Err(ApiError::SyncQueued);
+        };
+        Ok(self
+            .sync_wallet_with_permit(fvk, permit, cancel, true, |_, _| {})
+            .await?)
+    }
+
+    /// Like [`SyncService::client`], but waits for a sync slot instead of failing,
+    /// reporting when the sync is queued and how far it got
+    ///
+    /// The sync, and the lookups of the client returned, stop with
+    /// [`Error::Cancelled`] once `cancel` or the service is cancelled.
+    pub async fn client_with_progress(
+        &self,
+        fvk: &FullViewingKey,
+        cancel: CancellationToken,
+        mut progress: impl FnMut(SyncProgress),
+    ) -> Result<(DisclosureClient, u64)> {
+        if let Some(height) = self.synced_height(fvk).await {
+            return Ok((self.pool.get(fvk).await?.with_cancellation(cancel), height));
+        }
+        let permit = match self.syncs.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                progress(SyncProgress::Queued);
+                self.acquire_sync(&cancel).await?
+            }
+        };
+        self.sync_wallet_with_permit(fvk, permit, cancel, true, |synced_height, latest_height| {
+            progress(SyncProgress::Syncing {
+                synced_height,
+                latest_height,
+            })
+        })
+        .await
+    }
+
+    /// Syncs a single wallet once a sync slot is free, registering it if
+    /// `register` is set and it isn't already
+    pub async fn sync_wallet(&self, fvk: &FullViewingKey, register: bool) -> Result<u64> {
+        let cancel = self.shutdown.token();
+        let permit = self.acquire_sync(&cancel).await?;
+        let (_, height) = self
+            .sync_wallet_with_permit(fvk, permit, cancel, register, |_, _| {})
+            .await?;
+        Ok(height)
+    }
+
+    /// Returns the height a registered wallet is synced to, marking it as used
+    async fn synced_height(&self, fvk: &FullViewingKey) -> Option<u64> {
+        self.wallets
+            .write()
+            .await
+            .get_mut(&fvk_hash(fvk))
+            .map(|wallet| {
+                wallet.last_used = Instant::now();
+                wallet.synced_height
+            })
+    }
+
+    async fn acquire_sync(&self, cancel: &CancellationToken) -> Result<OwnedSemaphorePermit> {
+        tokio::select! {
+            permit = self.syncs.clone().acquire_owned() => {
+                Ok(permit.expect("sync semaphore is never closed"))
+            }
+            _ = cancel.cancelled() => Err(Error::Cancelled),
+        }
+    }
+
+    /// Syncs `fvk` in the background once a slot is free, unless it is already queued
+    fn queue_sync(self: &Arc<Self>, fvk: &FullViewingKey) {
+        let hash = fvk_hash(fvk);
+        if !self.queued.lock().unwrap().insert(hash.clone()) {
+            return;
+        }
+        log::info!(
+            "every sync slot is taken, queued the sync of {}",
+            fvk_hash_prefix(fvk)
+        );
+        let service = self.clone();
+        let fvk = fvk.clone();
+        self.shutdown.spawn(async move {
+            if let Err(err) = service.sync_wallet(&fvk, true).await {
+                log::warn!(
+                    "queued sync failed for {}: {}",
+                    fvk_hash_prefix(&fvk),
+                    err.details()
+                );
+            }
+            service.queued.lock().unwrap().remove(&hash);
+        });
+    }
+
+    async fn sync_wallet_with_permit(
+        &self,
+        fvk: &FullViewingKey,
+        _permit: OwnedSemaphorePermit,
+        cancel: CancellationToken,
+        register: bool,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<(DisclosureClient, u64)> {
+        let mut in_flight = InFlightSync::start(self, fvk_hash(fvk));
+        let dc = self.pool.get(fvk).await?.with_cancellation(cancel);
+        let mut latest_height = 0;
+        let height = dc
+            .sync_with_progress(|synced_height, latest| {
+                latest_height = latest;
+                progress(synced_height, latest)
+            })
+            .await?;
+        let latest_height = latest_height.max(height);
+        let mut wallets = self.wallets.write().await;
+        match wallets.get_mut(&fvk_hash(fvk)) {
+            Some(wallet) => {
+                wallet.synced_height = height;
+                wallet.latest_height = latest_height;
+            }
+            None if register => {
+                wallets.insert(
+                    fvk_hash(fvk),
+                    Wallet {
+                        fvk: fvk.clone(),
+                        synced_height: height,
+                        latest_height,
+                        last_used: Instant::now(),
+                    },
+                );
+            }
+            // the wallet was unregistered during the sync, so its client is
+            // evicted like that of an abandoned sync
+            None => return Ok((dc, height)),
+        }
+        in_flight.completed = true;
+        Ok((dc, height))
+    }
+
+    /// Returns how many blocks each registered wallet is behind the latest
+    /// height the node reported during any sync, in no particular order
+    pub async fn sync_lags(&self) -> Vec<u64> {
+        let wallets = self.wallets.read().await;
+        let latest_height = wallets
+            .values()
+            .map(|wallet| wallet.latest_height)
+            .max()
+            .unwrap_or_default();
+        wallets
+            .values()
+            .map(|wallet| latest_height.saturating_sub(wallet.synced_height))
+            .collect()
+    }
+
+    /// Unregisters `fvk` and evicts its client, so the wallet is no longer synced
+    ///
+    /// Syncs already in flight run to completion, without registering the wallet again
+    /// unless they were started by a disclosure.
+    pub async fn unregister(&self, fvk: &FullViewingKey) {
+        let hash = fvk_hash(fvk);
+        if self.wallets.write().await.remove(&hash).is_some() {
+            log::info!("unregistered {}", fvk_hash_prefix(fvk));
+        }
+        self.pool.evict(&hash, || false).await;
+    }
+
+    /// Unregisters every wallet that wasn't used within the pool's idle timeout
+    pub async fn unregister_idle(&self) {
+        let idle_timeout = self.pool.idle_timeout();
+        let mut wallets = self.wallets.write().await;
+        let before = wallets.len();
+        wallets.retain(|_, wallet| wallet.last_used.elapsed() < idle_timeout);
+        let unregistered = before - wallets.len();
+        if unregistered > 0 {
+            log::info!("unregistered {unregistered} idle wallets");
+        }
+    }
+
+    /// Syncs every registered wallet, at most `concurrency` at a time, after
+    /// unregistering the idle ones
+    pub async fn sync_all(&self) {
+        self.unregister_idle().await;
+        let fvks = self
+            .wallets
+            .read()
+            .await
+            .values()
+            .map(|wallet| wallet.fvk.clone())
+            .collect::<Vec<_>>();
+        futures::stream::iter(fvks)
+            .for_each_concurrent(self.concurrency, |fvk| async move {
+                // wallets unregistered in the meantime stay unregistered
+                match self.sync_wallet(&fvk, false).await {
+                    Ok(_) | Err(Error::Cancelled) => {}
+                    Err(err) => log::warn!(
+                        "background sync failed for {}: {}",
+                        fvk_hash_prefix(&fvk),
+                        err.details()
+                    ),
+                }
+            })
+            .await;
+    }
+
+    /// Runs [`SyncService::sync_all`] every `interval` until the service is
+    /// dropped or the server shuts down
+    pub fn spawn(self: &Arc<Self>) {
+        let service = Arc::downgrade(self);
+        let interval = self.interval.max(Duration::from_secs(1));
+        let shutdown = self.shutdown.clone();
+        self.shutdown.spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.requested() => return,
+                }
+                let Some(service) = service.upgrade() else {
+                    return;
+                };
+                service.sync_all().await;
+            }
+        });
+    }
+}
+
+/// Counts a sync while it is in flight, evicting the wallet's client once
+/// the last sync of a wallet that isn't registered ends without completing
+struct InFlightSync {
+    pool: Arc<ClientPool>,
+    wallets: Arc<RwLock<HashMap<String, Wallet>>>,
+    syncing: Arc<Mutex<HashMap<String, usize>>>,
+    hash: String,
+    completed: bool,
+}
+
+impl InFlightSync {
+    fn start(service: &SyncService, hash: String) -> Self {
+        *service
+            .syncing
+            .lock()
+            .unwrap()
+            .entry(hash.clone())
+            .or_default() += 1;
+        Self {
+            pool: service.pool.clone(),
+            wallets: service.wallets.clone(),
+            syncing: service.syncing.clone(),
+            hash,
+            completed: false,
+        }
+    }
+}
+
+impl Drop for InFlightSync {
+    fn drop(&mut self) {
+        {
+            let mut syncing = self.syncing.lock().unwrap();
+            let Some(count) = syncing.get_mut(&self.hash) else {
+                return;
+            };
+            *count -= 1;
+            if *count > 0 {
+                return;
+            }
+            syncing.remove(&self.hash);
+        }
+        // nothing is left to evict from once the runtime is gone
+        let (false, Ok(runtime)) = (self.completed, tokio::runtime::Handle::try_current()) else {
+            return;
+        };
+        // the view server keeps syncing for as long as its client is pooled
+        let pool = self.pool.clone();
+        let wallets = self.wallets.clone();
+        let syncing = self.syncing.clone();
+        let hash = std::mem::take(&mut self.hash);
+        runtime.spawn(async move {
+            if wallets.read().await.contains_key(&hash) {
+                return;
+            }
+            if pool
+                .evict(&hash, || syncing.lock().unwrap().contains_key(&hash))
+                .await
+            {
+                log::info!("evicted the disclosure client of an abandoned sync");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::api::server::test_util::FVK, std::str::FromStr};
+
+    #[tokio::test]
+    async fn test_sync_queued() {
+        let pool = Arc::new(ClientPool::new(
+            "http://127.0.0.1:1".to_string(),
+            Duration::from_secs(60),
+        ));
+        let service = Arc::new(SyncService::new(
+            pool,
+            1,
+            1,
+            Duration::from_secs(60),
+            Shutdown::default(),
+        ));
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+
+        // hold the only sync slot
+        let permit = service.syncs.clone().try_acquire_owned().unwrap();
+        assert!(matches!(
+            service.client(&fvk).await,
+            Err(ApiError::SyncQueued)
+        ));
+        assert!(service.queued.lock().unwrap().contains(&fvk_hash(&fvk)));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let waiting = tokio::spawn({
+            let service = service.clone();
+            let fvk = fvk.clone();
+            async move {
+                service
+                    .client_with_progress(&fvk, CancellationToken::new(), |progress| {
+                        let _ = tx.send(progress);
+                    })
+                    .await
+            }
+        });
+        assert_eq!(rx.recv().await, Some(SyncProgress::Queued));
+
+        // a queued sync gives up once it's cancelled, without waiting for a slot
+        let cancel = CancellationToken::new();
+        let cancelled = tokio::spawn({
+            let service = service.clone();
+            let fvk = fvk.clone();
+            let cancel = cancel.clone();
+            async move { service.client_with_progress(&fvk, cancel, |_| {}).await }
+        });
+        cancel.cancel();
+        assert!(matches!(cancelled.await.unwrap(), Err(Error::Cancelled)));
+
+        // once the slot frees up the syncs run, and fail as the node is unreachable
+        drop(permit);
+        assert!(matches!(
+            waiting.await.unwrap(),
+            Err(Error::NodeUnavailable(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unregister_idle() {
+        let pool = Arc::new(ClientPool::new(
+            "http://127.0.0.1:1".to_string(),
+            Duration::from_millis(100),
+        ));
+        let service = SyncService::new(pool, 1, 1, Duration::from_secs(60), Shutdown::default());
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+        service.wallets.write().await.insert(
+            fvk_hash(&fvk),
+            Wallet {
+                fvk: fvk.clone(),
+                synced_height: 10,
+                latest_height: 10,
+                last_used: Instant::now(),
+            },
+        );
+
+        // using a wallet for a disclosure keeps it registered
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(service.synced_height(&fvk).await, Some(10));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        service.unregister_idle().await;
+        assert_eq!(service.sync_lags().await.len(), 1);
+
+        // while a wallet that is only synced in the background is dropped
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        service.sync_all().await;
+        assert!(service.sync_lags().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unregister() {
+        let pool = Arc::new(ClientPool::new(
+            "http://127.0.0.1:1".to_string(),
+            Duration::from_secs(60),
+        ));
+        let service = SyncService::new(
+            pool.clone(),
+            1,
+            1,
+            Duration::from_secs(60),
+            Shutdown::default(),
+        );
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+        service.wallets.write().await.insert(
+            fvk_hash(&fvk),
+            Wallet {
+                fvk: fvk.clone(),
+                synced_height: 10,
+                latest_height: 10,
+                last_used: Instant::now(),
+            },
+        );
+        // the node is unreachable, but the client is pooled all the same
+        assert!(pool.get(&fvk).await.is_err());
+        assert_eq!(pool.len().await, 1);
+
+        service.unregister(&fvk).await;
+        assert!(service.sync_lags().await.is_empty());
+        assert!(pool.is_empty().await);
+        // so background syncs no longer sync the wallet
+        service.sync_all().await;
+        assert!(pool.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_sync_lags() {
+        let pool = Arc::new(ClientPool::new(
+            "http://127.0.0.1:1".to_string(),
+            Duration::from_secs(60),
+        ));
+        let service = SyncService::new(pool, 1, 1, Duration::from_secs(60), Shutdown::default());
+        assert!(service.sync_lags().await.is_empty());
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+        for (hash, synced_height, latest_height) in [("a", 90, 100), ("b", 120, 120)] {
+            service.wallets.write().await.insert(
+                hash.to_string(),
+                Wallet {
+                    fvk: fvk.clone(),
+                    synced_height,
+                    latest_height,
+                    last_used: Instant::now(),
+                },
+            );
+        }
+        // lags are relative to the latest height seen by any sync
+        let mut lags = service.sync_lags().await;
+        lags.sort();
+        assert_eq!(lags, [0, 30]);
+    }
+}
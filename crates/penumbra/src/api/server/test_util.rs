@@ -0,0 +1,71 @@
+//! Fixtures shared by the api server's tests
+
+use {
+    axum::{body::Body, response::Response, Router},
+    http::Request,
+    http_body_util::BodyExt,
+    serde::{de::DeserializeOwned, Serialize},
+    std::sync::{Arc, Mutex},
+    tower::ServiceExt,
+    tracing::subscriber::DefaultGuard,
+};
+
+/// Viewing key of a testnet wallet with transactions to disclose
+pub const FVK: &str = "penumbrafullviewingkey1jzwnl8k7hhqnvf06m4hfdwtsyc9ucce4nq6slpvxm8l9jgse0gg676654ea865dz4mn9ez33q3ysnedcplxey5g589cx4xl0duqkzrc0gqscq";
+
+/// Collects everything a test's subscriber writes, so tests can inspect what the server logs
+#[derive(Clone, Default)]
+pub struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    /// Captures the JSON logs of the current thread until the guard is dropped
+    pub fn json_logs() -> (Self, DefaultGuard) {
+        let captured = Self::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .finish();
+        (captured, tracing::subscriber::set_default(subscriber))
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl std::io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends `request` through `router`
+pub async fn send(router: &Router, request: Request<Body>) -> Response {
+    router.clone().oneshot(request).await.unwrap()
+}
+
+/// Builds a POST of `body` as JSON to `uri`
+pub fn post_json(uri: &str, body: &impl Serialize) -> Request<Body> {
+    Request::post(uri)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(body).unwrap()))
+        .unwrap()
+}
+
+/// Reads a response body as text
+pub async fn body_text(res: Response) -> String {
+    String::from_utf8(res.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap()
+}
+
+/// Reads a JSON response body
+pub async fn json_body<T: DeserializeOwned>(res: Response) -> T {
+    serde_json::from_slice(&res.into_body().collect().await.unwrap().to_bytes()).unwrap()
+}
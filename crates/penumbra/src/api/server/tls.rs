@@ -0,0 +1,319 @@
+use {
+    anyhow::{Context, Result},
+    camino::{Utf8Path, Utf8PathBuf},
+    rustls_pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    std::{
+        net::SocketAddr,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tokio::{
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+    },
+    tokio_rustls::{
+        rustls::{self, crypto::ring, server::WebPkiClientVerifier, RootCertStore},
+        server::TlsStream,
+        TlsAcceptor,
+    },
+};
+
+/// How long a client has to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Certificates the api listener terminates TLS with
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM file with the server certificate chain
+    pub cert: Utf8PathBuf,
+    /// PEM file with the server's private key
+    pub key: Utf8PathBuf,
+    /// PEM file with the CAs client certificates must be issued by, enables mTLS if set
+    pub client_ca: Option<Utf8PathBuf>,
+    /// how often the files are checked for changes
+    pub reload_interval: Duration,
+}
+
+impl TlsConfig {
+    fn files(&self) -> Vec<&Utf8Path> {
+        [Some(&self.cert), Some(&self.key), self.client_ca.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|path| path.as_path())
+            .collect()
+    }
+
+    /// Reads the certificates and builds an acceptor from them
+    fn acceptor(&self) -> Result<TlsAcceptor> {
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("failed to read certificates from {}", self.cert))?;
+        if certs.is_empty() {
+            anyhow::bail!("no certificates in {}", self.cert);
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key)
+            .with_context(|| format!("failed to read private key from {}", self.key))?;
+
+        let provider = Arc::new(ring::default_provider());
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .with_context(|| "failed to configure tls")?;
+        let builder = match &self.client_ca {
+            Some(client_ca) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(client_ca)
+                    .with_context(|| format!("failed to read {client_ca}"))?
+                {
+                    let cert =
+                        cert.with_context(|| format!("invalid certificate in {client_ca}"))?;
+                    roots
+                        .add(cert)
+                        .with_context(|| format!("invalid certificate in {client_ca}"))?;
+                }
+                let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider)
+                    .build()
+                    .with_context(|| "failed to build client certificate verifier")?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .with_context(|| format!("{} doesn't match {}", self.key, self.cert))?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// TLS terminating listener for [`axum::serve`]
+///
+/// Handshakes run in their own tasks so a slow client can't hold up
+/// accepting others, and the certificates are reloaded whenever the files
+/// change so renewed certificates are picked up without a restart.
+pub struct TlsListener {
+    local_addr: SocketAddr,
+    streams: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: TlsConfig) -> Result<Self> {
+        let local_addr = listener
+            .local_addr()
+            .with_context(|| "failed to get listener address")?;
+        let acceptor = Arc::new(RwLock::new(config.acceptor()?));
+        let (tx, streams) = mpsc::channel(64);
+        tokio::spawn(reload(config, acceptor.clone(), tx.clone()));
+        tokio::spawn(accept(listener, acceptor, tx));
+        Ok(Self {
+            local_addr,
+            streams,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.streams.recv().await {
+            Some(stream) => stream,
+            // the accept loop only stops once the listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+type StreamSender = mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>;
+
+async fn accept(listener: TcpListener, acceptor: Arc<RwLock<TlsAcceptor>>, tx: StreamSender) {
+    while !tx.is_closed() {
+        let (stream, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                log::warn!("failed to accept connection: {err}");
+                // usually out of file descriptors, give connections a chance to close
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.read().unwrap().clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = tx.send((stream, addr)).await;
+                }
+                Ok(Err(err)) => log::debug!("tls handshake with {addr} failed: {err}"),
+                Err(_) => log::debug!("tls handshake with {addr} timed out"),
+            }
+        });
+    }
+}
+
+/// Rebuilds the acceptor whenever the contents of the certificate files change
+async fn reload(config: TlsConfig, acceptor: Arc<RwLock<TlsAcceptor>>, tx: StreamSender) {
+    let read_files = || {
+        config
+            .files()
+            .into_iter()
+            .map(|path| std::fs::read(path).ok())
+            .collect::<Vec<_>>()
+    };
+    let mut loaded = read_files();
+    let mut interval =
+        tokio::time::interval(config.reload_interval.max(Duration::from_millis(100)));
+    while !tx.is_closed() {
+        interval.tick().await;
+        let files = read_files();
+        if files == loaded {
+            continue;
+        }
+        match config.acceptor() {
+            Ok(reloaded) => {
+                *acceptor.write().unwrap() = reloaded;
+                log::info!("reloaded tls certificates");
+                loaded = files;
+            }
+            // certificates are often renewed one file at a time, so keep
+            // serving the old ones until the new ones are consistent
+            Err(err) => log::warn!("failed to reload tls certificates: {err:#}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        axum::{routing::get, Router},
+        tokio::io::{AsyncReadExt, AsyncWriteExt},
+        tokio_rustls::{rustls::pki_types::ServerName, TlsConnector},
+    };
+
+    const CA: &str = include_str!("testdata/ca.pem");
+    const SERVER_CERT: &str = include_str!("testdata/server.pem");
+    const SERVER_KEY: &str = include_str!("testdata/server.key");
+    const RENEWED_CERT: &str = include_str!("testdata/server-renewed.pem");
+    const RENEWED_KEY: &str = include_str!("testdata/server-renewed.key");
+    const CLIENT_CERT: &str = include_str!("testdata/client.pem");
+    const CLIENT_KEY: &str = include_str!("testdata/client.key");
+
+    /// Writes the test certificates to a fresh directory and serves a router with them
+    async fn serve(name: &str, mtls: bool) -> (Utf8PathBuf, SocketAddr) {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("tls-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in [
+            ("ca.pem", CA),
+            ("server.pem", SERVER_CERT),
+            ("server.key", SERVER_KEY),
+        ] {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+        let listener = TlsListener::new(
+            TcpListener::bind("127.0.0.1:0").await.unwrap(),
+            TlsConfig {
+                cert: dir.join("server.pem"),
+                key: dir.join("server.key"),
+                client_ca: mtls.then(|| dir.join("ca.pem")),
+                reload_interval: Duration::from_millis(100),
+            },
+        )
+        .unwrap();
+        let addr = listener.local_addr;
+        let router = Router::new().route("/health", get(|| async { "ok" }));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (dir, addr)
+    }
+
+    /// Requests `/health` over TLS, returning the response and the server's certificate
+    async fn get_health(
+        addr: SocketAddr,
+        client_cert: bool,
+    ) -> std::io::Result<(String, CertificateDer<'static>)> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_slice(CA.as_bytes()).unwrap())
+            .unwrap();
+        let builder =
+            rustls::ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots);
+        let config = if client_cert {
+            builder
+                .with_client_auth_cert(
+                    vec![CertificateDer::from_pem_slice(CLIENT_CERT.as_bytes()).unwrap()],
+                    PrivateKeyDer::from_pem_slice(CLIENT_KEY.as_bytes()).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(
+                ServerName::try_from("localhost").unwrap(),
+                TcpStream::connect(addr).await?,
+            )
+            .await?;
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+            .await?;
+        let mut res = String::new();
+        stream.read_to_string(&mut res).await?;
+        let cert = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+        Ok((res, cert))
+    }
+
+    #[tokio::test]
+    async fn test_tls_listener_reloads_certificates() {
+        let (dir, addr) = serve("reload", false).await;
+        let (res, cert) = get_health(addr, false).await.unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(
+            cert,
+            CertificateDer::from_pem_slice(SERVER_CERT.as_bytes()).unwrap()
+        );
+
+        // a key that doesn't match the certificate yet leaves the old ones in place
+        std::fs::write(dir.join("server.key"), RENEWED_KEY).unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let (_, cert) = get_health(addr, false).await.unwrap();
+        assert_eq!(
+            cert,
+            CertificateDer::from_pem_slice(SERVER_CERT.as_bytes()).unwrap()
+        );
+
+        std::fs::write(dir.join("server.pem"), RENEWED_CERT).unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let (res, cert) = get_health(addr, false).await.unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(
+            cert,
+            CertificateDer::from_pem_slice(RENEWED_CERT.as_bytes()).unwrap()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tls_listener_requires_client_certificate() {
+        let (dir, addr) = serve("mtls", true).await;
+        let (res, _) = get_health(addr, true).await.unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK"));
+
+        // tls 1.3 client auth fails after the client considers the handshake done,
+        // so the rejection shows up as an error or an empty response
+        if let Ok((res, _)) = get_health(addr, false).await {
+            assert!(res.is_empty());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use {
+    crate::error::{Error, Result},
+    anyhow::{anyhow, Context},
+    camino::{Utf8Path, Utf8PathBuf},
+    chacha20poly1305::{
+        aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+        ChaCha20Poly1305, Key, Nonce,
+    },
+    penumbra_sdk_keys::FullViewingKey,
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{collections::HashMap, str::FromStr},
+    tokio::sync::RwLock,
+};
+
+/// Prefix of every wallet handle, so handles are recognizable in configs and requests
+const HANDLE_PREFIX: &str = "wallet_";
+
+/// Registered FVKs, encrypted at rest and looked up by opaque wallet handles
+///
+/// Only the sha256 of each handle is stored, so the store on its own can't be
+/// used to make disclosure requests, and the handle hash is bound to its
+/// ciphertext as associated data so entries can't be swapped around.
+pub struct WalletStore {
+    /// where the store is persisted, in-memory stores are lost on restart
+    path: Option<Utf8PathBuf>,
+    cipher: ChaCha20Poly1305,
+    wallets: RwLock<HashMap<String, Encrypted>>,
+}
+
+/// Data encrypted under the wallet key, bound to associated data it can't be decrypted without
+#[derive(Serialize, Deserialize)]
+pub struct Encrypted {
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Encrypted {
+    pub fn seal(cipher: &ChaCha20Poly1305, msg: &[u8], aad: &[u8]) -> anyhow::Result<Self> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg, aad })
+            .map_err(|_| anyhow!("failed to encrypt"))?;
+        Ok(Self {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, cipher: &ChaCha20Poly1305, aad: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let nonce = hex::decode(&self.nonce).with_context(|| "invalid nonce")?;
+        let ciphertext = hex::decode(&self.ciphertext).with_context(|| "invalid ciphertext")?;
+        cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| anyhow!("failed to decrypt, was the key changed?"))
+    }
+}
+
+/// Reads the hex encoded 32 byte key in `key_file`
+pub fn load_key(key_file: &Utf8Path) -> anyhow::Result<Key> {
+    let key =
+        std::fs::read_to_string(key_file).with_context(|| format!("failed to read {key_file}"))?;
+    let key = hex::decode(key.trim()).with_context(|| format!("invalid key in {key_file}"))?;
+    if key.len() != 32 {
+        anyhow::bail!("key in {key_file} must be 32 bytes");
+    }
+    Ok(*Key::from_slice(&key))
+}
+
+impl WalletStore {
+    /// Creates a store that only lives as long as the process, under a random key
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            cipher: ChaCha20Poly1305::new(&ChaCha20Poly1305::generate_key(&mut OsRng)),
+            wallets: Default::default(),
+        }
+    }
+
+    /// Loads the store at `path`, encrypted under `key`
+    pub fn load(path: Utf8PathBuf, key: &Key) -> anyhow::Result<Self> {
+        let wallets = if path.exists() {
+            let contents =
+                std::fs::read(&path).with_context(|| format!("failed to read {path}"))?;
+            serde_json::from_slice(&contents).with_context(|| format!("failed to parse {path}"))?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            path: Some(path),
+            cipher: ChaCha20Poly1305::new(key),
+            wallets: RwLock::new(wallets),
+        })
+    }
+
+    /// Registers `fvk` and returns a new handle for it
+    pub async fn register(&self, fvk: &FullViewingKey) -> Result<String> {
+        let mut handle = [0u8; 32];
+        OsRng.fill_bytes(&mut handle);
+        let handle = format!("{HANDLE_PREFIX}{}", hex::encode(handle));
+        let handle_hash = handle_hash(&handle);
+        let encrypted = Encrypted::seal(
+            &self.cipher,
+            fvk.to_string().as_bytes(),
+            handle_hash.as_bytes(),
+        )?;
+
+        let mut wallets = self.wallets.write().await;
+        wallets.insert(handle_hash, encrypted);
+        self.persist(&wallets).await?;
+        Ok(handle)
+    }
+
+    /// Returns the FVK registered under `handle`
+    pub async fn resolve(&self, handle: &str) -> Result<FullViewingKey> {
+        let handle_hash = handle_hash(handle);
+        let wallets = self.wallets.read().await;
+        self.decrypt(&wallets, &handle_hash)
+    }
+
+    /// Revokes `handle`, after which it can no longer be used for disclosures,
+    /// returning the FVK it was registered for
+    pub async fn revoke(&self, handle: &str) -> Result<FullViewingKey> {
+        let handle_hash = handle_hash(handle);
+        let mut wallets = self.wallets.write().await;
+        let fvk = self.decrypt(&wallets, &handle_hash)?;
+        wallets.remove(&handle_hash);
+        self.persist(&wallets).await?;
+        Ok(fvk)
+    }
+
+    fn decrypt(
+        &self,
+        wallets: &HashMap<String, Encrypted>,
+        handle_hash: &str,
+    ) -> Result<FullViewingKey> {
+        let encrypted = wallets.get(handle_hash).ok_or(Error::WalletNotFound)?;
+        let fvk = encrypted
+            .open(&self.cipher, handle_hash.as_bytes())
+            .with_context(|| "failed to decrypt wallet")?;
+        let fvk = String::from_utf8(fvk).with_context(|| "invalid fvk")?;
+        Ok(FullViewingKey::from_str(&fvk).map_err(|_| anyhow!("invalid fvk"))?)
+    }
+
+    async fn persist(&self, wallets: &HashMap<String, Encrypted>) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // write to a temporary file first so a crash never leaves a truncated store behind
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(wallets)?)
+            .await
+            .with_context(|| format!("failed to write {tmp_path}"))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .with_context(|| format!("failed to persist {path}"))
+    }
+}
+
+fn handle_hash(handle: &str) -> String {
+    hex::encode(Sha256::digest(handle))
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::api::server::test_util::FVK};
+
+    #[tokio::test]
+    async fn test_wallet_store_persists_encrypted() {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir()).unwrap();
+        let path = dir.join(format!("wallets-{}.json", std::process::id()));
+        let key_file = dir.join(format!("wallets-{}.key", std::process::id()));
+        std::fs::write(&key_file, hex::encode([7u8; 32])).unwrap();
+
+        let fvk = FullViewingKey::from_str(FVK).unwrap();
+        let key = load_key(&key_file).unwrap();
+        let store = WalletStore::load(path.clone(), &key).unwrap();
+        let handle = store.register(&fvk).await.unwrap();
+        assert!(handle.starts_with(HANDLE_PREFIX));
+        assert_eq!(store.resolve(&handle).await.unwrap().to_string(), FVK);
+
+        // neither the fvk nor the handle are stored in the clear
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&FVK["penumbrafullviewingkey1".len()..][..16]));
+        assert!(!contents.contains(&handle[HANDLE_PREFIX.len()..]));
+
+        let store = WalletStore::load(path.clone(), &key).unwrap();
+        assert_eq!(store.resolve(&handle).await.unwrap().to_string(), FVK);
+        store.revoke(&handle).await.unwrap();
+        assert!(matches!(
+            store.resolve(&handle).await,
+            Err(Error::WalletNotFound)
+        ));
+        assert!(matches!(
+            store.revoke(&handle).await,
+            Err(Error::WalletNotFound)
+        ));
+
+        let store = WalletStore::load(path.clone(), &key).unwrap();
+        assert!(matches!(
+            store.resolve(&handle).await,
+            Err(Error::WalletNotFound)
+        ));
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(key_file).unwrap();
+    }
+}
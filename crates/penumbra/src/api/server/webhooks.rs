@@ -0,0 +1,508 @@
+use {
+    anyhow::{Context, Result},
+    camino::Utf8Path,
+    chrono::Utc,
+    common::models::CallbackAttempt,
+    hmac::{Hmac, Mac},
+    reqwest::dns::{Addrs, Name, Resolve, Resolving},
+    sha2::Sha256,
+    std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        sync::Arc,
+        time::Duration,
+    },
+};
+
+/// Header carrying the signature of a webhook delivery, `t=<unix seconds>,v1=<hex hmac>`
+pub const SIGNATURE_HEADER: &str = "x-disclosure-signature";
+/// Header carrying the id of the job a webhook delivery is for
+pub const JOB_ID_HEADER: &str = "x-disclosure-job-id";
+/// Header carrying the status of the job, `completed` or `failed`
+pub const JOB_STATUS_HEADER: &str = "x-disclosure-job-status";
+
+/// Longest delay between two delivery attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// POSTs the outcome of disclosure jobs to their callback URLs
+///
+/// Every delivery is signed with HMAC-SHA256 over `<timestamp>.<body>`, so
+/// receivers can check it came from this server with [`verify_signature`].
+/// Failed deliveries are retried with exponential backoff, starting at
+/// `initial_backoff`, for at most `max_attempts` attempts.
+///
+/// Callback URLs are chosen by callers, so deliveries are only made to public
+/// addresses, unless the operator allowed the host in `allowed_hosts`. The
+/// addresses are checked when connecting, so a host can't resolve to a
+/// public address when the job is submitted and a private one later, and
+/// redirects are never followed.
+pub struct WebhookSender {
+    client: reqwest::Client,
+    secret: Vec<u8>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    allowed_hosts: Arc<AllowedHosts>,
+}
+
+/// Hosts callbacks may be delivered to even though they aren't public
+#[derive(Default)]
+struct AllowedHosts(Vec<String>);
+
+impl AllowedHosts {
+    fn contains(&self, host: &str) -> bool {
+        self.0
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+}
+
+/// Resolves callback hosts, refusing to connect to non-public addresses
+struct PublicResolver(Arc<AllowedHosts>);
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let allowed = self.0.contains(&host);
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .collect::<Vec<_>>();
+            if !allowed && !addrs.iter().all(|addr| is_public(addr.ip())) {
+                return Err(format!("{host} resolves to a non-public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl WebhookSender {
+    pub fn new(
+        secret: Vec<u8>,
+        max_attempts: u32,
+        initial_backoff: Duration,
+        allowed_hosts: Vec<String>,
+    ) -> Result<Self> {
+        let allowed_hosts = Arc::new(AllowedHosts(allowed_hosts));
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .redirect(reqwest::redirect::Policy::none())
+                .no_proxy()
+                .dns_resolver(Arc::new(PublicResolver(allowed_hosts.clone())))
+                .build()
+                .with_context(|| "failed to build webhook client")?,
+            secret,
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            allowed_hosts,
+        })
+    }
+
+    /// Loads the signing secret from `secret_file`
+    pub fn load(
+        secret_file: &Utf8Path,
+        max_attempts: u32,
+        initial_backoff: Duration,
+        allowed_hosts: Vec<String>,
+    ) -> Result<Self> {
+        let secret = std::fs::read_to_string(secret_file)
+            .with_context(|| format!("failed to read {secret_file}"))?;
+        let secret = secret.trim();
+        if secret.is_empty() {
+            anyhow::bail!("webhook secret in {secret_file} is empty");
+        }
+        Self::new(
+            secret.as_bytes().to_vec(),
+            max_attempts,
+            initial_backoff,
+            allowed_hosts,
+        )
+    }
+
+    /// Checks that callbacks can be delivered to `url`, an http or https URL
+    /// whose host only resolves to public addresses or is allowed
+    pub async fn check_url(&self, url: &str) -> std::result::Result<(), &'static str> {
+        let url = reqwest::Url::parse(url)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or("callbackUrl must be an http or https url")?;
+        let host = url
+            .host_str()
+            .ok_or("callbackUrl must be an http or https url")?;
+        if self.allowed_hosts.contains(host) {
+            return Ok(());
+        }
+        let addrs = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => tokio::net::lookup_host((host, 0))
+                .await
+                .map_err(|_| "callbackUrl host can't be resolved")?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        if addrs.is_empty() || !addrs.into_iter().all(is_public) {
+            return Err("callbackUrl must point to a public address");
+        }
+        Ok(())
+    }
+
+    /// Signs `body` as of `timestamp`, returning the value of the signature header
+    pub fn sign(&self, timestamp: i64, body: &[u8]) -> String {
+        format!(
+            "t={timestamp},v1={}",
+            hex::encode(
+                signature_mac(&self.secret, timestamp, body)
+                    .finalize()
+                    .into_bytes()
+            )
+        )
+    }
+
+    /// Delivers `body` to `url`, retrying until it is accepted or the attempts run out.
+    ///
+    /// `record` is called after every attempt, and whether the body was delivered is returned.
+    pub async fn deliver(
+        &self,
+        url: &str,
+        job_id: &str,
+        job_status: &str,
+        body: Vec<u8>,
+        mut record: impl FnMut(CallbackAttempt),
+    ) -> bool {
+        let mut backoff = self.initial_backoff;
+        for attempt in 1..=self.max_attempts {
+            let attempted_at = Utc::now().timestamp();
+            // ip addresses aren't resolved, so they're never seen by the resolver
+            if let Err(error) = self.check_url(url).await {
+                log::warn!("webhook delivery {attempt} for job {job_id} refused: {error}");
+                record(CallbackAttempt {
+                    attempted_at,
+                    status_code: None,
+                    error: Some(error.to_string()),
+                });
+                return false;
+            }
+            let res = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, self.sign(attempted_at, &body))
+                .header(JOB_ID_HEADER, job_id)
+                .header(JOB_STATUS_HEADER, job_status)
+                .body(body.clone())
+                .send()
+                .await;
+            let (status_code, error) = match res {
+                Ok(res) if res.status().is_success() => {
+                    record(CallbackAttempt {
+                        attempted_at,
+                        status_code: Some(res.status().as_u16().into()),
+                        error: None,
+                    });
+                    return true;
+                }
+                Ok(res) => (
+                    Some(res.status().as_u16().into()),
+                    format!("callback responded with {}", res.status()),
+                ),
+                // without the source chain, which may include the url
+                Err(err) => (None, error_kind(&err).to_string()),
+            };
+            log::warn!("webhook delivery {attempt} for job {job_id} failed: {error}");
+            record(CallbackAttempt {
+                attempted_at,
+                status_code,
+                error: Some(error),
+            });
+            if attempt < self.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+        false
+    }
+}
+
+/// Whether `ip` is reachable on the internet, rather than loopback, private,
+/// link-local, shared, multicast or otherwise reserved
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match embedded_v4(ip) {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+/// IPv4 address that IPv4-mapped (`::ffff:0:0/96`), IPv4-compatible (`::/96`),
+/// NAT64 (`64:ff9b::/96`) and 6to4 (`2002::/16`) addresses route to
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0 | 0xffff, ..] | [0x64, 0xff9b, 0, 0, 0, 0, ..] => Some(Ipv4Addr::new(
+            octets[12], octets[13], octets[14], octets[15],
+        )),
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network", shared address space, benchmarking and reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // documentation
+        || (ip.segments()[0] == 0x2001 && ip.segments()[1] == 0xdb8))
+}
+
+fn error_kind(err: &reqwest::Error) -> &'static str {
+    if err.is_timeout() {
+        "callback timed out"
+    } else if err.is_connect() {
+        "failed to connect to callback"
+    } else {
+        "failed to send callback"
+    }
+}
+
+fn signature_mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Checks a webhook signature header against the delivered body.
+///
+/// Signatures older than `tolerance` are rejected to prevent replays.
+pub fn verify_signature(secret: &[u8], header: &str, body: &[u8], tolerance: Duration) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return false;
+    };
+    if (Utc::now().timestamp() - timestamp).unsigned_abs() > tolerance.as_secs() {
+        return false;
+    }
+    signature_mac(secret, timestamp, body)
+        .verify_slice(&signature)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        axum::{extract::State, http::HeaderMap, routing::post, Router},
+        http::StatusCode,
+        std::sync::{Arc, Mutex},
+    };
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>;
+
+    /// Stand-in callback endpoint that fails the first request and accepts the rest
+    async fn spawn_receiver() -> (String, Received) {
+        let received = Received::default();
+        let router = Router::new()
+            .route(
+                "/callback",
+                post(
+                    |State(received): State<Received>,
+                     headers: HeaderMap,
+                     body: axum::body::Bytes| async move {
+                        let mut received = received.lock().unwrap();
+                        received.push((headers, body.to_vec()));
+                        if received.len() == 1 {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::NO_CONTENT
+                        }
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{addr}/callback"), received)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_retries() {
+        let (url, received) = spawn_receiver().await;
+        let sender = WebhookSender::new(
+            b"secret".to_vec(),
+            3,
+            Duration::from_millis(10),
+            vec!["127.0.0.1".to_string()],
+        )
+        .unwrap();
+
+        let mut attempts = vec![];
+        let delivered = sender
+            .deliver(&url, "job", "completed", b"{}".to_vec(), |attempt| {
+                attempts.push(attempt)
+            })
+            .await;
+        assert!(delivered);
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].status_code, Some(500));
+        assert!(attempts[0].error.is_some());
+        assert_eq!(attempts[1].status_code, Some(204));
+        assert!(attempts[1].error.is_none());
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[1];
+        assert_eq!(headers[JOB_ID_HEADER], "job");
+        assert_eq!(headers[JOB_STATUS_HEADER], "completed");
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        assert!(verify_signature(
+            b"secret",
+            signature,
+            body,
+            Duration::from_secs(60)
+        ));
+        assert!(!verify_signature(
+            b"other secret",
+            signature,
+            body,
+            Duration::from_secs(60)
+        ));
+        assert!(!verify_signature(
+            b"secret",
+            signature,
+            b"{\"tampered\":true}",
+            Duration::from_secs(60)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_gives_up() {
+        // nothing listens on port 1
+        let sender = WebhookSender::new(
+            b"secret".to_vec(),
+            2,
+            Duration::from_millis(10),
+            vec!["127.0.0.1".to_string()],
+        )
+        .unwrap();
+        let mut attempts = vec![];
+        let delivered = sender
+            .deliver(
+                "http://127.0.0.1:1/callback",
+                "job",
+                "failed",
+                b"{}".to_vec(),
+                |attempt| attempts.push(attempt),
+            )
+            .await;
+        assert!(!delivered);
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts.iter().all(|attempt| attempt.status_code.is_none()));
+    }
+    #[tokio::test]
+    async fn test_webhook_delivery_refuses_private_hosts() {
+        let (url, received) = spawn_receiver().await;
+        let sender =
+            WebhookSender::new(b"secret".to_vec(), 2, Duration::from_millis(10), vec![]).unwrap();
+        for url in [
+            url.as_str(),
+            "http://localhost:1/callback",
+            "http://10.0.0.1/callback",
+            "http://100.64.0.1/callback",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/callback",
+            "http://[::ffff:127.0.0.1]/callback",
+            "http://[::10.0.0.1]/callback",
+            "http://[64:ff9b::a9fe:a9fe]/callback",
+            "http://[2002:c0a8:101::1]/callback",
+            "http://[fd00::1]/callback",
+            "ftp://example.com/callback",
+        ] {
+            assert!(sender.check_url(url).await.is_err(), "{url} was accepted");
+        }
+        for url in [
+            "https://93.184.215.14/callback",
+            "https://[2606:2800:21f:cb07:6820:80da:af6b:8b2c]/callback",
+            // addresses embedding a public IPv4 address
+            "https://[::ffff:93.184.215.14]/callback",
+            "https://[64:ff9b::5db8:d70e]/callback",
+            "https://[2002:5db8:d70e::1]/callback",
+        ] {
+            assert!(sender.check_url(url).await.is_ok(), "{url} was refused");
+        }
+
+        // hosts that resolve to private addresses are refused when connecting too
+        let port = url.split(':').nth(2).unwrap();
+        let url = format!("http://localhost:{port}");
+        let mut attempts = vec![];
+        let delivered = sender
+            .deliver(&url, "job", "completed", b"{}".to_vec(), |attempt| {
+                attempts.push(attempt)
+            })
+            .await;
+        assert!(!delivered);
+        assert_eq!(attempts.len(), 1);
+        assert!(received.lock().unwrap().is_empty());
+        let res = sender.client.post(&url).send().await;
+        assert!(res.is_err());
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_ignores_redirects() {
+        let router = Router::new().route(
+            "/callback",
+            post(|| async { axum::response::Redirect::temporary("http://10.0.0.1/") }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let sender = WebhookSender::new(
+            b"secret".to_vec(),
+            1,
+            Duration::from_millis(10),
+            vec!["127.0.0.1".to_string()],
+        )
+        .unwrap();
+        let mut attempts = vec![];
+        let delivered = sender
+            .deliver(
+                &format!("http://{addr}/callback"),
+                "job",
+                "completed",
+                b"{}".to_vec(),
+                |attempt| attempts.push(attempt),
+            )
+            .await;
+        assert!(!delivered);
+        assert_eq!(attempts[0].status_code, Some(307));
+    }
+}
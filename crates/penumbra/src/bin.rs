@@ -1,32 +1,281 @@
 use {
     anyhow::{Context, Result},
-    clap::{Parser, Subcommand},
-    penumbra_disclosure::{api, client::DisclosureClient},
+    camino::Utf8PathBuf,
+    clap::{Args, Parser, Subcommand},
+    penumbra_disclosure::{
+        api::{
+            self,
+            server::{
+                auth::{AuthConfig, Authenticator},
+                config::{ServerConfig, DEFAULT_GRPC_URL},
+            },
+        },
+        client::{normalize_hash, DisclosureClient},
+    },
+    std::time::Duration,
 };
 
 #[derive(Parser)]
 struct Cli {
+    #[arg(long, help = "penumbra grpc node [default: http://localhost:8080]")]
+    grpc_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Settings of the api server, each overriding the config file and `FARADAY_*` variables
+#[derive(Args)]
+struct ApiArgs {
+    #[arg(long, help = "TOML file to load the api server config from")]
+    config: Option<Utf8PathBuf>,
+    #[arg(long, help = "url to expose the api on [default: localhost:1337]")]
+    listen_url: Option<String>,
+    #[arg(
+        long,
+        help = "seconds a cached disclosure client may sit idle before it is dropped [default: 600]"
+    )]
+    client_idle_timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "maximum number of registered wallets to sync concurrently [default: 4]"
+    )]
+    sync_concurrency: Option<usize>,
+    #[arg(
+        long,
+        help = "seconds between background syncs of registered wallets [default: 30]"
+    )]
+    sync_interval: Option<u64>,
+    #[arg(
+        long,
+        help = "maximum number of wallet syncs to run at once, inline and in the background [default: 8]"
+    )]
+    max_concurrent_syncs: Option<usize>,
+    #[arg(
+        long,
+        help = "maximum number of concurrent lookups when disclosing a batch of transactions [default: 16]"
+    )]
+    lookup_concurrency: Option<usize>,
+    #[arg(
+        long,
+        help = "maximum number of disclosed transactions to cache, disabled if 0 [default: 10000]"
+    )]
+    disclosure_cache_size: Option<usize>,
+    #[arg(
+        long,
+        help = "TOML file with the api keys and bearer token secret accepted by the api"
+    )]
+    auth_config: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "file to persist registered wallets to, by default they are only kept in memory"
+    )]
+    wallet_store: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "file with the hex encoded 32 byte key registered wallets are encrypted under"
+    )]
+    wallet_key_file: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "maximum number of disclosure jobs to run concurrently [default: 4]"
+    )]
+    job_workers: Option<usize>,
+    #[arg(
+        long,
+        help = "maximum number of disclosure jobs queued or running, further jobs are rejected [default: 1000]"
+    )]
+    max_queued_jobs: Option<usize>,
+    #[arg(
+        long,
+        help = "directory to persist disclosure jobs to, encrypted under --wallet-key-file. By default they are only kept in memory"
+    )]
+    job_dir: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "seconds finished disclosure jobs are kept before they are removed [default: 86400]"
+    )]
+    job_retention: Option<u64>,
+    #[arg(
+        long,
+        help = "file with the secret webhook deliveries are signed with, enables callback urls on jobs"
+    )]
+    webhook_secret_file: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "maximum number of attempts to deliver a webhook [default: 5]"
+    )]
+    webhook_max_attempts: Option<u32>,
+    #[arg(
+        long,
+        help = "seconds before the first webhook retry, doubled after every failed attempt [default: 1]"
+    )]
+    webhook_initial_backoff: Option<u64>,
+    #[arg(
+        long = "webhook-allowed-host",
+        value_delimiter = ',',
+        help = "host callbacks may be delivered to even if it isn't public, repeatable [default: none]"
+    )]
+    webhook_allowed_hosts: Vec<String>,
+    #[arg(long, help = "least severe level to log [default: info]")]
+    log_level: Option<String>,
+    #[arg(
+        long,
+        help = "file to append JSON logs to, including one line per request"
+    )]
+    log_file: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "OTLP/HTTP collector to export traces to, e.g. http://localhost:4318/v1/traces"
+    )]
+    otlp_endpoint: Option<String>,
+    #[arg(
+        long,
+        help = "chain id the node must be on for /ready to succeed, any chain if unset"
+    )]
+    chain_id: Option<String>,
+    #[arg(long, help = "PEM certificate chain to serve the api over tls with")]
+    tls_cert: Option<Utf8PathBuf>,
+    #[arg(long, help = "PEM private key of the tls certificate")]
+    tls_key: Option<Utf8PathBuf>,
     #[arg(
         long,
-        help = "penumbra grpc node",
-        default_value = "http://localhost:8080"
+        help = "PEM file with the CAs client certificates must be issued by, enables mtls"
     )]
-    grpc_url: String,
+    tls_client_ca: Option<Utf8PathBuf>,
+    #[arg(
+        long,
+        help = "seconds between checks of the tls files for renewed certificates [default: 30]"
+    )]
+    tls_reload_interval: Option<u64>,
+    #[arg(
+        long = "cors-allowed-origin",
+        value_delimiter = ',',
+        help = "origin browsers may call the api from, `*` for any, repeatable [default: none]"
+    )]
+    cors_allowed_origins: Vec<String>,
+    #[arg(
+        long,
+        help = "largest accepted request body in bytes [default: 1048576]"
+    )]
+    max_body_size: Option<usize>,
+    #[arg(
+        long,
+        help = "seconds a request may take until its response starts [default: 120]"
+    )]
+    request_timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "maximum number of transaction hashes in one batch disclosure [default: 1000]"
+    )]
+    max_transaction_hashes: Option<usize>,
+    #[arg(
+        long,
+        help = "disclosure requests each api key may make per minute, unlimited if 0 [default: 0]"
+    )]
+    rate_limit_per_minute: Option<u32>,
+    #[arg(
+        long,
+        help = "disclosure requests each api key may make at once [default: 10]"
+    )]
+    rate_limit_burst: Option<u32>,
+    #[arg(
+        long,
+        help = "seconds in-flight requests, syncs and jobs may take to finish on shutdown before they are cancelled [default: 30]"
+    )]
+    shutdown_timeout: Option<u64>,
+}
 
-    #[command(subcommand)]
-    command: Commands,
+impl ApiArgs {
+    /// Loads the config file and environment overrides, then applies the flags given
+    fn config(self, grpc_url: Option<String>) -> Result<ServerConfig> {
+        let mut cfg = ServerConfig::load(self.config.as_deref())?;
+        macro_rules! set {
+            ($($field:ident),*) => {$(
+                if let Some(value) = self.$field {
+                    cfg.$field = value;
+                }
+            )*};
+        }
+        macro_rules! set_some {
+            ($($field:ident),*) => {$(
+                if self.$field.is_some() {
+                    cfg.$field = self.$field;
+                }
+            )*};
+        }
+        if let Some(grpc_url) = grpc_url {
+            cfg.grpc_url = grpc_url;
+        }
+        set!(
+            listen_url,
+            log_level,
+            client_idle_timeout,
+            sync_concurrency,
+            sync_interval,
+            max_concurrent_syncs,
+            lookup_concurrency,
+            disclosure_cache_size,
+            job_workers,
+            max_queued_jobs,
+            job_retention,
+            webhook_max_attempts,
+            webhook_initial_backoff,
+            tls_reload_interval,
+            max_body_size,
+            request_timeout,
+            max_transaction_hashes,
+            rate_limit_per_minute,
+            rate_limit_burst,
+            shutdown_timeout
+        );
+        set_some!(
+            chain_id,
+            log_file,
+            otlp_endpoint,
+            auth_config,
+            wallet_store,
+            wallet_key_file,
+            job_dir,
+            webhook_secret_file,
+            tls_cert,
+            tls_key,
+            tls_client_ca
+        );
+        if !self.cors_allowed_origins.is_empty() {
+            cfg.cors_allowed_origins = self.cors_allowed_origins;
+        }
+        if !self.webhook_allowed_hosts.is_empty() {
+            cfg.webhook_allowed_hosts = self.webhook_allowed_hosts;
+        }
+        Ok(cfg)
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "run the disclosure api service")]
     Api {
+        #[command(flatten)]
+        args: ApiArgs,
+    },
+    #[command(about = "inspect the api server config")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    #[command(about = "issue a bearer token for the api")]
+    IssueToken {
+        #[arg(long, help = "TOML file with the bearer token secret")]
+        auth_config: Utf8PathBuf,
+        #[arg(long, help = "name of the caller the token is issued to")]
+        subject: String,
         #[arg(
             long,
-            help = "url to expose the api on",
-            default_value = "localhost:1337"
+            help = "seconds until the token expires",
+            default_value_t = 86400
         )]
-        listen_url: String,
+        ttl: u64,
     },
     #[command(about = "generate a disclosure bundle for a transaction")]
     DiscloseTransaction {
@@ -35,24 +284,84 @@ enum Commands {
             help = "full vieweing key that can decrypt at least part of the transaction"
         )]
         full_viewing_key: String,
-        #[arg(long, help = "the transaction hash to generate the bundle for")]
+        #[arg(
+            long,
+            help = "the transaction hash to generate the bundle for, optionally prefixed with 0x"
+        )]
         transaction_hash: String,
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigCommands {
+    #[command(about = "validate the api server config and print it with secrets masked")]
+    Check {
+        #[command(flatten)]
+        args: ApiArgs,
+    },
+}
+
 #[tokio::main]
 pub async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Api { listen_url } => api::server::start_api(cli.grpc_url, listen_url).await,
+        Commands::Api { args } => {
+            let cfg = args.config(cli.grpc_url)?;
+            let opts = cfg.options().with_context(|| "invalid config")?;
+            let log_file = cfg.log_file.as_ref().map_or("", |path| path.as_str());
+            // flushes the remaining spans once the server has shut down
+            let _otlp = match &cfg.otlp_endpoint {
+                Some(endpoint) => Some(
+                    common::utils::init_log_with_otlp(
+                        &cfg.log_level,
+                        log_file,
+                        "penumbra-disclosure",
+                        endpoint,
+                    )
+                    .with_context(|| "failed to create otlp exporter")?,
+                ),
+                None => {
+                    common::utils::init_log(&cfg.log_level, log_file);
+                    None
+                }
+            };
+            for name in &cfg.ignored_env {
+                log::warn!("ignoring {name}, which doesn't override a config key");
+            }
+            api::server::start_api(cfg.grpc_url, cfg.listen_url, opts).await
+        }
+        Commands::Config {
+            command: ConfigCommands::Check { args },
+        } => {
+            let cfg = args.config(cli.grpc_url)?;
+            cfg.options().with_context(|| "invalid config")?;
+            for name in &cfg.ignored_env {
+                eprintln!("warning: ignoring {name}, which doesn't override a config key");
+            }
+            print!("{}", cfg.to_masked_toml()?);
+            Ok(())
+        }
+        Commands::IssueToken {
+            auth_config,
+            subject,
+            ttl,
+        } => {
+            let auth = Authenticator::new(&AuthConfig::load(&auth_config)?);
+            println!("{}", auth.issue_token(&subject, Duration::from_secs(ttl))?);
+            Ok(())
+        }
         Commands::DiscloseTransaction {
             full_viewing_key,
             transaction_hash,
         } => {
-            let dc = DisclosureClient::new(&cli.grpc_url, &full_viewing_key.parse()?).await?;
-
-            let dc = dc.lock().await;
+            // fail on a malformed hash before paying for a sync
+            let transaction_hash = normalize_hash(&transaction_hash)?;
+            let dc = DisclosureClient::new(
+                cli.grpc_url.as_deref().unwrap_or(DEFAULT_GRPC_URL),
+                &full_viewing_key.parse()?,
+            )
+            .await?;
 
             dc.sync()
                 .await
@@ -0,0 +1,154 @@
+use {
+    anyhow::{Context, Result},
+    camino::Utf8PathBuf,
+    std::collections::BTreeMap,
+    tokio::{
+        io::AsyncWriteExt,
+        sync::{Mutex, RwLock},
+    },
+};
+
+/// Persistent cache of block height -> block timestamp (unix seconds)
+///
+/// Block times never change once a block is committed, so they are cached on
+/// disk alongside the view database and only queried from the node once.
+///
+/// The cache is an append-only log with one `<height> <timestamp>` line per
+/// block, so persisting new entries costs the same however large it is, and
+/// lookups never wait on the disk. It isn't bounded: only the heights of the
+/// wallet's own transactions are cached, so it grows with the wallet's
+/// history at a few bytes per transaction, like the view database next to it.
+pub struct BlockTimeCache {
+    path: Utf8PathBuf,
+    times: RwLock<BTreeMap<u64, i64>>,
+    /// serializes appends, so concurrent writers never interleave their lines
+    log: Mutex<()>,
+}
+
+impl BlockTimeCache {
+    /// Loads the cache stored at `path`, starting empty if it doesn't exist yet
+    ///
+    /// A line left incomplete by a crash is dropped, and the log rewritten without it.
+    pub fn load(path: Utf8PathBuf) -> Result<Self> {
+        let mut times = BTreeMap::new();
+        if path.exists() {
+            let contents =
+                std::fs::read_to_string(&path).with_context(|| format!("failed to read {path}"))?;
+            // the last line is only complete once its newline was written
+            let (lines, mut complete) = match contents.rsplit_once('\n') {
+                Some((lines, "")) => (lines, true),
+                Some((lines, _)) => (lines, false),
+                None => ("", contents.is_empty()),
+            };
+            for line in lines.lines() {
+                match parse_line(line) {
+                    Some((height, time)) => {
+                        times.insert(height, time);
+                    }
+                    None => complete = false,
+                }
+            }
+            if !complete {
+                log::warn!("dropping incomplete entries of {path}");
+                // write to a temporary file first so a crash never leaves a truncated log behind
+                let tmp_path = path.with_extension("tmp");
+                std::fs::write(&tmp_path, format_lines(&times))
+                    .with_context(|| format!("failed to write {tmp_path}"))?;
+                std::fs::rename(&tmp_path, &path)
+                    .with_context(|| format!("failed to persist {path}"))?;
+            }
+        }
+        Ok(Self {
+            path,
+            times: RwLock::new(times),
+            log: Mutex::new(()),
+        })
+    }
+
+    pub async fn get(&self, height: u64) -> Option<i64> {
+        self.times.read().await.get(&height).copied()
+    }
+
+    /// Inserts the given block times, appending the new ones to the log
+    pub async fn extend(&self, times: impl IntoIterator<Item = (u64, i64)>) -> Result<()> {
+        let added = {
+            let mut cached = self.times.write().await;
+            times
+                .into_iter()
+                .filter(|(height, time)| cached.insert(*height, *time).is_none())
+                .collect::<BTreeMap<_, _>>()
+        };
+        if added.is_empty() {
+            return Ok(());
+        }
+        let _log = self.log.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("failed to open {}", self.path))?;
+        file.write_all(format_lines(&added).as_bytes())
+            .await
+            .with_context(|| format!("failed to write {}", self.path))?;
+        // tokio only writes the file in the background until it's flushed
+        file.flush()
+            .await
+            .with_context(|| format!("failed to write {}", self.path))
+    }
+}
+
+fn parse_line(line: &str) -> Option<(u64, i64)> {
+    let (height, time) = line.split_once(' ')?;
+    Some((height.parse().ok()?, time.parse().ok()?))
+}
+
+fn format_lines(times: &BTreeMap<u64, i64>) -> String {
+    times
+        .iter()
+        .map(|(height, time)| format!("{height} {time}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_block_time_cache_persists() {
+        let path = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("block-times-{}.log", std::process::id()));
+
+        let cache = BlockTimeCache::load(path.clone()).unwrap();
+        assert_eq!(cache.get(10).await, None);
+        cache
+            .extend([(10, 1745289093), (11, 1745289098)])
+            .await
+            .unwrap();
+        assert_eq!(cache.get(10).await, Some(1745289093));
+        // only new entries are appended
+        cache
+            .extend([(11, 1745289098), (12, 1745289103)])
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "10 1745289093\n11 1745289098\n12 1745289103\n"
+        );
+
+        let cache = BlockTimeCache::load(path.clone()).unwrap();
+        assert_eq!(cache.get(11).await, Some(1745289098));
+
+        // an entry cut off by a crash is dropped, without corrupting later appends
+        std::fs::write(&path, "10 1745289093\n11 17452").unwrap();
+        let cache = BlockTimeCache::load(path.clone()).unwrap();
+        assert_eq!(cache.get(11).await, None);
+        cache.extend([(11, 1745289098)]).await.unwrap();
+        let cache = BlockTimeCache::load(path.clone()).unwrap();
+        assert_eq!(cache.get(10).await, Some(1745289093));
+        assert_eq!(cache.get(11).await, Some(1745289098));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,12 +1,19 @@
+mod block_times;
+
 use {
-    crate::types::TransactionType,
-    anyhow::{Context, Result},
+    crate::{
+        error::{Error, Result},
+        metrics::metrics,
+        types::TransactionType,
+    },
+    anyhow::{anyhow, Context},
+    block_times::BlockTimeCache,
     camino::Utf8PathBuf,
     common::{
         self,
         models::{counterparty::Role, transaction::Protocol, Counterparty, Transaction},
     },
-    futures::StreamExt,
+    futures::{Stream, StreamExt},
     penumbra_sdk_keys::{AddressView, FullViewingKey},
     penumbra_sdk_proto::{
         box_grpc_svc::{self, BoxGrpcService},
@@ -15,92 +22,351 @@ use {
         },
         view::v1::{
             view_service_client::ViewServiceClient, view_service_server::ViewServiceServer,
+            TransactionInfoByHashRequest,
         },
     },
-    penumbra_sdk_view::{ViewClient, ViewServer},
+    penumbra_sdk_txhash::TransactionId,
+    penumbra_sdk_view::{TransactionInfo, ViewClient, ViewServer},
     sha3::{Digest, Sha3_256},
-    std::{collections::HashMap, str::FromStr, sync::Arc},
-    tokio::sync::Mutex,
+    std::{collections::HashMap, future::Future, sync::Arc, time::Instant},
+    tokio::sync::OnceCell,
+    tokio_util::sync::CancellationToken,
     tonic::transport::Channel,
+    tracing::{field, Instrument},
 };
 
+/// Block times of a batch disclosure, by height, each looked up at most once
+type SharedBlockTimes =
+    std::sync::Mutex<HashMap<u64, Arc<OnceCell<std::result::Result<i64, String>>>>>;
+
+/// Returns the hex encoded sha3 hash of the fvk, used to identify a wallet
+/// without exposing the fvk itself
+pub fn fvk_hash(fvk: &FullViewingKey) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(fvk.to_string());
+    hex::encode(hasher.finalize())
+}
+
+/// Characters of the FVK hash that are logged, enough to correlate requests
+/// without making the wallet identifiable from the logs alone
+const FVK_HASH_PREFIX_LEN: usize = 8;
+
+/// Returns the prefix of [`fvk_hash`] that logs and spans identify a wallet by
+pub fn fvk_hash_prefix(fvk: &FullViewingKey) -> String {
+    fvk_hash(fvk)[..FVK_HASH_PREFIX_LEN].to_string()
+}
+
+/// Number of hex characters in a transaction hash
+const TRANSACTION_HASH_LEN: usize = 64;
+
+/// Normalizes a hex encoded transaction hash to lowercase without the optional `0x` prefix
+///
+/// Fails with [`Error::InvalidHash`] unless the hash is 32 bytes of hex.
+pub fn normalize_hash(hash: &str) -> Result<String> {
+    let hex = hash.trim();
+    let hex = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+    if hex.len() != TRANSACTION_HASH_LEN || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::InvalidHash(anyhow!(
+            "expected {TRANSACTION_HASH_LEN} hex characters, optionally prefixed with 0x"
+        )));
+    }
+    Ok(hex.to_ascii_lowercase())
+}
+
+/// Client used to generate disclosure bundles for the transactions of a single wallet
+///
+/// The client is cheap to clone, and clones share the same view server so
+/// syncs and disclosures can run concurrently without any locking.
 #[derive(Clone)]
 pub struct DisclosureClient {
-    view: Arc<Mutex<ViewServiceClient<BoxGrpcService>>>,
-    tpc: Arc<Mutex<TendermintProxyServiceClient<Channel>>>,
+    view_server: ViewServer,
+    block_times: Arc<BlockTimeCache>,
+    tpc: TendermintProxyServiceClient<Channel>,
     fvk: FullViewingKey,
+    cancel: CancellationToken,
 }
 
 impl DisclosureClient {
-    /// We need to wrap in Arc<RwLock<T>> because ViewServiceClient is not Sync
-    pub async fn new(url: &str, fvk: &FullViewingKey) -> Result<Arc<Mutex<Self>>> {
+    #[tracing::instrument(name = "DisclosureClient::new", skip_all, fields(fvk_hash = %fvk_hash_prefix(fvk)))]
+    pub async fn new(url: &str, fvk: &FullViewingKey) -> Result<Self> {
         // store the db on disk using the filename as the sha3 hash of the fvk
         // this way we arent storing the actual fvk on disk in plaintext
         // this also allows the DisclosureClient to be reused within the api service
         // and not have conflicts
 
-        let mut hasher = Sha3_256::new();
-        hasher.update(fvk.to_string());
-        let storage_path = Utf8PathBuf::from_str(&hex::encode(hasher.finalize()))?;
+        let storage_path = Utf8PathBuf::from(fvk_hash(fvk));
+        let block_times =
+            BlockTimeCache::load(Utf8PathBuf::from(format!("{storage_path}.block-times.log")))
+                .with_context(|| "failed to load block time cache")?;
         let registry_path = storage_path.join("registry.json");
         let registry_path = if registry_path.exists() {
             Some(registry_path)
         } else {
             None
         };
+        let node = url.parse().with_context(|| "invalid grpc url")?;
         let view_server =
-            ViewServer::load_or_initialize(Some(storage_path), registry_path, fvk, url.parse()?)
+            ViewServer::load_or_initialize(Some(storage_path), registry_path, fvk, node)
                 .await
-                .with_context(|| "failed to create view server")?;
-
-        let svc: ViewServiceServer<ViewServer> = ViewServiceServer::new(view_server);
-        let view_service = ViewServiceClient::new(box_grpc_svc::local(svc));
+                .with_context(|| "failed to create view server")
+                .map_err(|err| {
+                    metrics().record_upstream_error("view_server_init");
+                    Error::NodeUnavailable(err)
+                })?;
 
-        Ok(Arc::new(Mutex::new(Self {
-            view: Arc::new(Mutex::new(view_service)),
-            tpc: Arc::new(Mutex::new(
-                TendermintProxyServiceClient::connect(url.to_string())
-                    .await
-                    .with_context(|| "failed to connect to proxy")?,
-            )),
+        Ok(Self {
+            view_server,
+            block_times: Arc::new(block_times),
+            tpc: TendermintProxyServiceClient::connect(url.to_string())
+                .await
+                .with_context(|| "failed to connect to proxy")
+                .map_err(|err| {
+                    metrics().record_upstream_error("proxy_connect");
+                    Error::NodeUnavailable(err)
+                })?,
             fvk: fvk.clone(),
-        })))
+            cancel: CancellationToken::new(),
+        })
     }
 
-    pub async fn sync(&self) -> Result<()> {
-        let view = self.view.clone();
-        let mut view = view.lock().await;
-        let view: &mut dyn ViewClient = &mut *view;
-        let mut stream = view.status_stream().await?;
-        while let Some(Ok(_)) = stream.next().await {}
-        Ok(())
+    /// Returns a clone of the client whose syncs and node queries fail with
+    /// [`Error::Cancelled`] as soon as `cancel` is cancelled
+    pub fn with_cancellation(&self, cancel: CancellationToken) -> Self {
+        Self {
+            cancel,
+            ..self.clone()
+        }
     }
 
+    /// Runs `fut` unless the client is cancelled first
+    async fn cancellable<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(Error::Cancelled),
+            result = fut => result,
+        }
+    }
+
+    /// Returns a view service client backed by the shared view server.
+    ///
+    /// ViewServiceClient is not Sync, so rather than sharing one client behind a lock
+    /// every operation gets its own, which only clones the handles inside the view server.
+    fn view(&self) -> ViewServiceClient<BoxGrpcService> {
+        let svc: ViewServiceServer<ViewServer> = ViewServiceServer::new(self.view_server.clone());
+        ViewServiceClient::new(box_grpc_svc::local(svc))
+    }
+
+    /// Waits for the view server to catch up with the chain, returning the synced height
+    pub async fn sync(&self) -> Result<u64> {
+        self.sync_with_progress(|_, _| {}).await
+    }
+
+    /// Like [`DisclosureClient::sync`], calling `progress` with the synced and the
+    /// latest known block height as the sync advances
+    pub async fn sync_with_progress(&self, progress: impl FnMut(u64, u64)) -> Result<u64> {
+        let span = tracing::info_span!(
+            "DisclosureClient::sync",
+            fvk_hash = %fvk_hash_prefix(&self.fvk),
+            synced_height = field::Empty,
+        );
+        let started = Instant::now();
+        let height = self
+            .cancellable(self.sync_inner(progress))
+            .instrument(span.clone())
+            .await;
+        metrics().observe_sync(height.is_ok(), started.elapsed());
+        if let Ok(height) = &height {
+            span.record("synced_height", height);
+        }
+        height
+    }
+
+    async fn sync_inner(&self, mut progress: impl FnMut(u64, u64)) -> Result<u64> {
+        let mut view = self.view();
+        let mut stream = ViewClient::status_stream(&mut view).await.map_err(|err| {
+            metrics().record_upstream_error("status_stream");
+            Error::SyncFailed(err)
+        })?;
+        while let Some(Ok(status)) = stream.next().await {
+            progress(status.full_sync_height, status.latest_known_block_height);
+        }
+        Ok(ViewClient::status(&mut view)
+            .await
+            .map_err(|err| {
+                metrics().record_upstream_error("status");
+                Error::SyncFailed(err)
+            })?
+            .full_sync_height)
+    }
+
+    /// Discloses a transaction, whose hash may be given in any form [`normalize_hash`] accepts
+    #[tracing::instrument(name = "DisclosureClient::transaction", skip(self))]
     pub async fn transaction(&self, hash: &str) -> Result<Transaction> {
-        let txn = {
-            let view = self.view.clone();
-            let mut view = view.lock().await;
-            let view: &mut dyn ViewClient = &mut *view;
-            view.transaction_info_by_hash(hash.parse().with_context(|| "failed to parse hash")?)
-                .await
-                .with_context(|| "failed to get tx hash")?
+        let result = async {
+            let hash = normalize_hash(hash)?;
+            let txn = self.transaction_info(&hash).await?;
+            let time = self.block_time(txn.height).await?;
+            self.disclose(&hash, txn, time)
+        }
+        .await;
+        metrics().record_disclosure(&result);
+        result
+    }
+
+    /// Discloses a batch of transactions, returning one result per hash in the same order.
+    /// Hashes are normalized like in [`DisclosureClient::transaction`].
+    ///
+    /// See [`DisclosureClient::transactions_stream`] for how lookups are batched.
+    #[tracing::instrument(
+        name = "DisclosureClient::transactions",
+        skip_all,
+        fields(transactions = hashes.len())
+    )]
+    pub async fn transactions(
+        &self,
+        hashes: &[String],
+        concurrency: usize,
+    ) -> Vec<Result<Transaction>> {
+        let mut results = self
+            .transactions_stream(hashes, concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Discloses a batch of transactions, yielding each result along with the
+    /// index of its hash as soon as it's ready
+    ///
+    /// Transaction lookups and block time queries are issued concurrently, at most
+    /// `concurrency` at a time, and the block time is queried once per distinct height.
+    pub fn transactions_stream<'a>(
+        &'a self,
+        hashes: &'a [String],
+        concurrency: usize,
+    ) -> impl Stream<Item = (usize, Result<Transaction>)> + Send + 'a {
+        let times = Arc::new(SharedBlockTimes::default());
+        // collect the futures up front, a borrowing closure inside the stream
+        // trips up the Send checks on the handler future
+        let lookups = hashes
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| {
+                let times = times.clone();
+                async move {
+                    let result = async {
+                        let hash = normalize_hash(hash)?;
+                        let txn = self.transaction_info(&hash).await?;
+                        let time = self.shared_block_time(&times, txn.height).await?;
+                        self.disclose(&hash, txn, time)
+                    }
+                    .await;
+                    metrics().record_disclosure(&result);
+                    (index, result)
+                }
+            })
+            .collect::<Vec<_>>();
+        futures::stream::iter(lookups).buffer_unordered(concurrency.max(1))
+    }
+
+    #[tracing::instrument(name = "view.transaction_info_by_hash", skip(self))]
+    async fn transaction_info(&self, hash: &str) -> Result<TransactionInfo> {
+        let id = hash
+            .parse::<TransactionId>()
+            .map_err(|err| Error::InvalidHash(anyhow!(err)))?;
+        // query the view service directly rather than through ViewClient so a
+        // missing transaction can be told apart from a failed lookup
+        let response = self
+            .cancellable(async {
+                Ok(self
+                    .view()
+                    .transaction_info_by_hash(TransactionInfoByHashRequest {
+                        id: Some(id.into()),
+                    })
+                    .await)
+            })
+            .await?;
+        let tx_info = match response {
+            Ok(res) => res.into_inner().tx_info,
+            Err(status) if status.code() == tonic::Code::NotFound => None,
+            Err(status) => {
+                metrics().record_upstream_error("transaction_info_by_hash");
+                return Err(Error::Internal(
+                    anyhow!(status).context("failed to get transaction info"),
+                ));
+            }
         };
-        let time = {
-            let tpc = self.tpc.clone();
-            let mut tpc = tpc.lock().await;
-            tpc.get_block_by_height(GetBlockByHeightRequest {
-                height: txn.height as i64,
+        let tx_info = tx_info.ok_or(Error::TransactionNotFound)?;
+        Ok(TransactionInfo::try_from(tx_info)
+            .map_err(|err| anyhow!(err).context("failed to decode transaction info"))?)
+    }
+
+    /// Returns the unix timestamp, in seconds, of the block at `height`
+    async fn block_time(&self, height: u64) -> Result<i64> {
+        if let Some(time) = self.block_times.get(height).await {
+            return Ok(time);
+        }
+        let time = self.query_block_time(height).await?;
+        if let Err(err) = self.block_times.extend([(height, time)]).await {
+            log::warn!("failed to persist block time cache {err:#}");
+        }
+        Ok(time)
+    }
+
+    /// Returns the block time at `height` through `times`, so it's only looked up
+    /// once however many transactions of the batch are in that block
+    ///
+    /// Errors are shared as well, as they are the same for every transaction in the block.
+    async fn shared_block_time(&self, times: &SharedBlockTimes, height: u64) -> Result<i64> {
+        let cell = times
+            .lock()
+            .expect("block time lock poisoned")
+            .entry(height)
+            .or_default()
+            .clone();
+        let time = cell
+            .get_or_init(|| async { self.block_time(height).await.map_err(|err| err.details()) })
+            .await;
+        match time {
+            Ok(time) => Ok(*time),
+            Err(_) if self.cancel.is_cancelled() => Err(Error::Cancelled),
+            Err(err) => Err(Error::NodeUnavailable(anyhow!("{err}"))),
+        }
+    }
+
+    #[tracing::instrument(name = "tendermint_proxy.get_block_by_height", skip(self))]
+    async fn query_block_time(&self, height: u64) -> Result<i64> {
+        let response = self
+            .cancellable(async {
+                self.tpc
+                    .clone()
+                    .get_block_by_height(GetBlockByHeightRequest {
+                        height: height as i64,
+                    })
+                    .await
+                    .with_context(|| "failed to query blockheight")
+                    .map_err(|err| {
+                        metrics().record_upstream_error("get_block_by_height");
+                        Error::NodeUnavailable(err)
+                    })
             })
-            .await
-            .with_context(|| "failed to query blockheight")?
+            .await?;
+        let time = response
             .into_inner()
             .block
             .with_context(|| "block is None")?
             .header
             .with_context(|| "header is None")?
             .time
-            .with_context(|| "time is None")?
-        };
+            .with_context(|| "time is None")?;
+        Ok(time.seconds)
+    }
+
+    /// Builds the disclosure bundle for a transaction the view server has already decrypted
+    fn disclose(&self, hash: &str, txn: TransactionInfo, time: i64) -> Result<Transaction> {
         let mut assets: HashMap<&penumbra_sdk_asset::asset::Id, common::models::Asset> =
             Default::default();
         for (asset_id, denom_metadata) in txn.perspective.denoms.iter() {
@@ -150,7 +416,7 @@ impl DisclosureClient {
             chain_id: txn.view.body_view.transaction_parameters.chain_id,
             counterparties: vec![],
             // todo: should we foramt the timestamp into a human readable value?
-            timestamp: format!("{}", time.seconds),
+            timestamp: format!("{time}"),
             metadata: if metadata.is_empty() {
                 None
             } else {
@@ -182,7 +448,7 @@ impl DisclosureClient {
 
             let denom_metadata = assets
                 .get(&balance_info.asset_id)
-                .with_context(|| format!("failed to get metadata for {}", balance_info.asset_id))?;
+                .ok_or_else(|| Error::UnknownAsset(balance_info.asset_id.to_string()))?;
 
             tx.counterparties.push(Counterparty {
                 // this is not correct, need a better way to determine if it is the recipient or receiver
@@ -203,7 +469,7 @@ impl DisclosureClient {
 
 #[cfg(test)]
 mod test {
-    use common::models::Asset;
+    use {common::models::Asset, std::str::FromStr};
 
     use super::*;
     #[tokio::test]
@@ -213,7 +479,6 @@ mod test {
         let dc = DisclosureClient::new("http://localhost:8080/", &fvk)
             .await
             .unwrap();
-        let dc = dc.lock().await;
         dc.sync().await.unwrap();
 
         let tx_info = dc
@@ -249,4 +514,63 @@ mod test {
         }));
         println!("{}", serde_json::to_string(&tx_info).unwrap());
     }
+
+    #[test]
+    fn test_normalize_hash() {
+        const TX_HASH: &str = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+
+        for hash in [
+            TX_HASH.to_string(),
+            format!("0x{TX_HASH}"),
+            format!("0X{}", TX_HASH.to_uppercase()),
+            format!(" {TX_HASH}\n"),
+        ] {
+            assert_eq!(normalize_hash(&hash).unwrap(), TX_HASH);
+        }
+        for hash in [
+            "",
+            "0x",
+            "0xabc123",
+            &TX_HASH[1..],
+            &format!("{TX_HASH}00"),
+            &format!("0x0x{}", &TX_HASH[2..]),
+            &TX_HASH.replace('c', "g"),
+        ] {
+            assert!(matches!(normalize_hash(hash), Err(Error::InvalidHash(..))));
+        }
+    }
+
+    #[test]
+    fn test_disclosure_client_is_shareable() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<DisclosureClient>();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_disclosure_client_concurrent() {
+        const TX_HASH: &str = "c888fe430188c9a83aa450ab7f647c51f6224caf16e3b8b25177d5d9d300ccaf";
+        let fvk = FullViewingKey::from_str("penumbrafullviewingkey1jzwnl8k7hhqnvf06m4hfdwtsyc9ucce4nq6slpvxm8l9jgse0gg676654ea865dz4mn9ez33q3ysnedcplxey5g589cx4xl0duqkzrc0gqscq").unwrap();
+
+        let dc = DisclosureClient::new("http://localhost:8080/", &fvk)
+            .await
+            .unwrap();
+        dc.sync().await.unwrap();
+
+        // a sync and several disclosures in flight at once, each on its own task
+        let sync = tokio::spawn({
+            let dc = dc.clone();
+            async move { dc.sync().await }
+        });
+        let disclosures = (0..8)
+            .map(|_| {
+                let dc = dc.clone();
+                tokio::spawn(async move { dc.transaction(TX_HASH).await })
+            })
+            .collect::<Vec<_>>();
+
+        for tx_info in futures::future::join_all(disclosures).await {
+            assert_eq!(tx_info.unwrap().unwrap().transaction_hash, TX_HASH);
+        }
+        sync.await.unwrap().unwrap();
+    }
 }
@@ -0,0 +1,89 @@
+/// Errors that can occur while generating disclosure bundles
+///
+/// Each variant maps to a stable machine-readable identifier, see [`Error::error_type`],
+/// which the api uses for the `code` and `errorTypes` fields of its error responses.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("transaction not found for the given full viewing key")]
+    TransactionNotFound,
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("wallet not found")]
+    WalletNotFound,
+    #[error("job not found")]
+    JobNotFound,
+    #[error("interrupted by a server restart")]
+    Interrupted,
+    #[error("cancelled before completing")]
+    Cancelled,
+    #[error("invalid full viewing key")]
+    InvalidFvk(#[source] anyhow::Error),
+    #[error("invalid transaction hash")]
+    InvalidHash(#[source] anyhow::Error),
+    #[error("failed to synchronize the view service")]
+    SyncFailed(#[source] anyhow::Error),
+    #[error("penumbra node is unavailable")]
+    NodeUnavailable(#[source] anyhow::Error),
+    #[error("missing metadata for asset {0}")]
+    UnknownAsset(String),
+    #[error("internal error")]
+    Internal(#[from] anyhow::Error),
+}
+
+impl Error {
+    /// Machine-readable identifier of the error
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            Self::TransactionNotFound => "transaction_not_found",
+            Self::InvalidRequest(..) => "invalid_request",
+            Self::WalletNotFound => "wallet_not_found",
+            Self::JobNotFound => "job_not_found",
+            Self::Interrupted => "interrupted",
+            Self::Cancelled => "cancelled",
+            Self::InvalidFvk(..) => "invalid_fvk",
+            Self::InvalidHash(..) => "invalid_hash",
+            Self::SyncFailed(..) => "sync_failed",
+            Self::NodeUnavailable(..) => "node_unavailable",
+            Self::UnknownAsset(..) => "unknown_asset",
+            Self::Internal(..) => "internal",
+        }
+    }
+
+    /// Formats the error along with its chain of sources, for server side logs only.
+    ///
+    /// Sources can echo back request contents, so anything resembling a full
+    /// viewing key is redacted. Clients should only ever see the [`Display`]
+    /// output, which never includes the sources.
+    ///
+    /// [`Display`]: std::fmt::Display
+    pub fn details(&self) -> String {
+        let mut details = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(err) = source {
+            details.push_str(&format!(": {err}"));
+            source = err.source();
+        }
+        redact_fvks(&details)
+    }
+}
+
+/// Replaces every bech32m encoded full viewing key in `s` with a placeholder
+pub fn redact_fvks(s: &str) -> String {
+    const FVK_PREFIX: &str = "penumbrafullviewingkey";
+
+    let mut redacted = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find(FVK_PREFIX) {
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("[redacted fvk]");
+        rest = &rest[start + FVK_PREFIX.len()..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
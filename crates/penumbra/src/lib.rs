@@ -4,7 +4,9 @@
 #[cfg(any(feature = "api-server", feature = "api-client"))]
 pub mod api;
 pub mod client;
+pub mod error;
+pub mod metrics;
 pub mod types;
 
 //#[cfg(feature = "cli")]
-//pub mod bin;
\ No newline at end of file
+//pub mod bin;
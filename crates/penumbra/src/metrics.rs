@@ -0,0 +1,282 @@
+//! Process wide metrics, rendered in the Prometheus text exposition format
+
+use {
+    crate::error::Result,
+    common::models::Transaction,
+    std::{
+        collections::BTreeMap,
+        fmt::Write,
+        sync::{LazyLock, Mutex},
+        time::Duration,
+    },
+};
+
+/// Latency buckets, in seconds, for request and sync durations
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Returns the metrics shared by the whole process
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    http_requests: Counter,
+    http_request_duration: Histogram,
+    disclosures: Counter,
+    sync_duration: Histogram,
+    upstream_errors: Counter,
+    cache_lookups: Counter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            http_requests: Counter::new(
+                "disclosure_http_requests_total",
+                "HTTP requests handled, by route and status",
+                &["method", "route", "status"],
+            ),
+            http_request_duration: Histogram::new(
+                "disclosure_http_request_duration_seconds",
+                "Time taken to respond to HTTP requests, by route",
+                &["method", "route"],
+            ),
+            disclosures: Counter::new(
+                "disclosure_transactions_total",
+                "Transactions disclosed, by outcome and error type",
+                &["outcome", "error_type"],
+            ),
+            sync_duration: Histogram::new(
+                "disclosure_sync_duration_seconds",
+                "Time taken to sync a wallet's view, by outcome",
+                &["outcome"],
+            ),
+            upstream_errors: Counter::new(
+                "disclosure_upstream_errors_total",
+                "Failed calls to the Penumbra node, by call",
+                &["call"],
+            ),
+            cache_lookups: Counter::new(
+                "disclosure_cache_lookups_total",
+                "Lookups in the disclosure cache, by result",
+                &["result"],
+            ),
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .inc(&[method, route, &status.to_string()]);
+        self.http_request_duration
+            .observe(&[method, route], elapsed.as_secs_f64());
+    }
+
+    pub fn record_disclosure(&self, result: &Result<Transaction>) {
+        match result {
+            Ok(_) => self.disclosures.inc(&["success", ""]),
+            Err(err) => self.disclosures.inc(&["error", err.error_type()]),
+        }
+    }
+
+    pub fn observe_sync(&self, succeeded: bool, elapsed: Duration) {
+        let outcome = if succeeded { "success" } else { "error" };
+        self.sync_duration
+            .observe(&[outcome], elapsed.as_secs_f64());
+    }
+
+    pub fn record_upstream_error(&self, call: &str) {
+        self.upstream_errors.inc(&[call]);
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        self.cache_lookups.inc(&[if hit { "hit" } else { "miss" }]);
+    }
+
+    /// Renders every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.http_requests.render(&mut out);
+        self.http_request_duration.render(&mut out);
+        self.disclosures.render(&mut out);
+        self.sync_duration.render(&mut out);
+        self.upstream_errors.render(&mut out);
+        self.cache_lookups.render(&mut out);
+        out
+    }
+}
+
+/// Appends a gauge to `out`, for values that are read from their source when scraped
+pub fn render_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (Vec<(&'static str, String)>, f64)>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{} {value}", format_labels(&labels));
+    }
+}
+
+struct Counter {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl Counter {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            values: Default::default(),
+        }
+    }
+
+    fn inc(&self, values: &[&str]) {
+        let key = values.iter().map(|value| value.to_string()).collect();
+        *self.values.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let (name, help) = (self.name, self.help);
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+        for (values, count) in self.values.lock().unwrap().iter() {
+            let labels = label_pairs(self.labels, values);
+            let _ = writeln!(out, "{name}{} {count}", format_labels(&labels));
+        }
+    }
+}
+
+struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, Buckets>>,
+}
+
+#[derive(Default)]
+struct Buckets {
+    /// observations per bucket, not cumulative
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            values: Default::default(),
+        }
+    }
+
+    fn observe(&self, values: &[&str], value: f64) {
+        let key = values.iter().map(|value| value.to_string()).collect();
+        let mut histograms = self.values.lock().unwrap();
+        let buckets = histograms.entry(key).or_insert_with(|| Buckets {
+            counts: vec![0; DURATION_BUCKETS.len()],
+            ..Default::default()
+        });
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| value <= *bound) {
+            buckets.counts[bucket] += 1;
+        }
+        buckets.sum += value;
+        buckets.count += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let (name, help) = (self.name, self.help);
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} histogram");
+        for (values, buckets) in self.values.lock().unwrap().iter() {
+            let labels = label_pairs(self.labels, values);
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(&buckets.counts) {
+                cumulative += count;
+                let mut labels = labels.clone();
+                labels.push(("le", bound.to_string()));
+                let _ = writeln!(out, "{name}_bucket{} {cumulative}", format_labels(&labels));
+            }
+            let mut inf_labels = labels.clone();
+            inf_labels.push(("le", "+Inf".to_string()));
+            let labels = format_labels(&labels);
+            let _ = writeln!(
+                out,
+                "{name}_bucket{} {}\n{name}_sum{labels} {}\n{name}_count{labels} {}",
+                format_labels(&inf_labels),
+                buckets.count,
+                buckets.sum,
+                buckets.count
+            );
+        }
+    }
+}
+
+fn label_pairs(names: &[&'static str], values: &[String]) -> Vec<(&'static str, String)> {
+    names.iter().copied().zip(values.iter().cloned()).collect()
+}
+
+fn format_labels(labels: &[(&'static str, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", labels.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Metrics::new();
+        metrics.observe_request("GET", "/health", 200, Duration::from_millis(20));
+        metrics.observe_request("GET", "/health", 200, Duration::from_secs(1000));
+        metrics.record_upstream_error("get_block_by_height");
+        let out = metrics.render();
+
+        assert!(out.contains(
+            "disclosure_http_requests_total{method=\"GET\",route=\"/health\",status=\"200\"} 2\n"
+        ));
+        assert!(out.contains(
+            "disclosure_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",le=\"0.025\"} 1\n"
+        ));
+        assert!(out.contains(
+            "disclosure_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(out.contains(
+            "disclosure_http_request_duration_seconds_count{method=\"GET\",route=\"/health\"} 2\n"
+        ));
+        assert!(out.contains("disclosure_upstream_errors_total{call=\"get_block_by_height\"} 1\n"));
+
+        let mut out = String::new();
+        render_gauge(
+            &mut out,
+            "test_gauge",
+            "A gauge",
+            [(vec![("wallet", "a\"b".to_string())], 3.0)],
+        );
+        assert_eq!(
+            out,
+            "# HELP test_gauge A gauge\n# TYPE test_gauge gauge\ntest_gauge{wallet=\"a\\\"b\"} 3\n"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+#! /bin/bash
+# Regenerates openapi.json, which the API server serves at /openapi.json, from openapi.yaml
+cd "$(dirname "$0")/.."
+
+python3 -c 'import json, yaml; json.dump(yaml.safe_load(open("openapi.yaml")), open("openapi.json", "w"), indent=2); open("openapi.json", "a").write("\n")'